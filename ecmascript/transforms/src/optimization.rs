@@ -1,7 +1,10 @@
 pub use self::{
     inline_globals::InlineGlobals,
     json_parse::JsonParse,
-    simplify::{expr_simplifier, simplifier},
+    simplify::{
+        dce::{self, dce_with, dce_with_comments, Config},
+        expr_simplifier, simplifier, simplifier_with_comments,
+    },
 };
 
 mod inline_globals;
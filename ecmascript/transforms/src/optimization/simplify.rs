@@ -1,9 +1,9 @@
 //! Ported from closure compiler.
 pub use self::dce::dce;
-use self::expr::SimplifyExpr;
+use self::{dce::dce_with_comments, expr::SimplifyExpr};
 use crate::pass::Pass;
 use ast::*;
-use swc_common::{Fold, FoldWith};
+use swc_common::{comments::Comments, Fold, FoldWith};
 
 pub mod dce;
 mod expr;
@@ -18,13 +18,28 @@ pub fn expr_simplifier() -> impl Pass + 'static {
 /// Ported from `PeepholeRemoveDeadCode` and `PeepholeFoldConstants` of google
 /// closure compiler.
 pub fn simplifier() -> impl Pass + 'static {
-    Simplifier
+    Simplifier { comments: None }
 }
 
-struct Simplifier;
+/// Same as [simplifier], but calls annotated with `/*#__PURE__*/` in
+/// `comments` are treated as pure.
+pub fn simplifier_with_comments(comments: &Comments) -> impl Pass + '_ {
+    Simplifier {
+        comments: Some(comments),
+    }
+}
 
-impl Fold<Program> for Simplifier {
+struct Simplifier<'a> {
+    comments: Option<&'a Comments>,
+}
+
+impl<'a> Fold<Program> for Simplifier<'a> {
     fn fold(&mut self, p: Program) -> Program {
-        p.fold_with(&mut expr_simplifier()).fold_with(&mut dce())
+        let p = p.fold_with(&mut expr_simplifier());
+
+        match self.comments {
+            Some(comments) => p.fold_with(&mut dce_with_comments(Default::default(), comments)),
+            None => p.fold_with(&mut dce()),
+        }
     }
 }
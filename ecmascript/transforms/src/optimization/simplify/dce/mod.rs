@@ -1,44 +1,242 @@
+use self::usage::Scope;
 use crate::{
     pass::Pass,
     util::{StmtLike, *},
 };
 use ast::*;
-use std::{cmp::min, iter::once};
-use swc_atoms::js_word;
+use fxhash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use std::{cmp::min, iter::once, mem::replace};
+use swc_atoms::{js_word, JsWord};
 use swc_common::{
-    fold::VisitWith, util::move_map::MoveMap, Fold, FoldWith, Spanned, Visit, DUMMY_SP,
+    comments::{CommentKind, Comments},
+    fold::VisitWith,
+    util::move_map::MoveMap,
+    Fold, FoldWith, Span, Spanned, Visit, DUMMY_SP,
 };
 
 #[cfg(test)]
 mod tests;
+mod usage;
 
 /// Ported from `PeepholeRemoveDeadCode` of google closure compiler.
 pub fn dce() -> impl Pass + 'static {
-    Remover::default()
+    dce_with(Default::default())
+}
+
+/// Same as [dce], but with a custom configuration.
+pub fn dce_with(config: Config) -> impl Pass + 'static {
+    Remover {
+        config,
+        ..Default::default()
+    }
+}
+
+/// Same as [dce_with], but calls annotated with `/*#__PURE__*/` in `comments`
+/// are treated as pure.
+pub fn dce_with_comments(config: Config, comments: &Comments) -> impl Pass + '_ {
+    Remover {
+        config,
+        comments: Some(comments),
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Config {
+    /// If true, `debugger` statements are removed.
+    #[serde(default)]
+    pub drop_debugger: bool,
+
+    /// If true, top-level bindings of a script or a module may be removed.
+    ///
+    /// Top-level bindings of a script can be used by other scripts, so this is
+    /// off by default.
+    #[serde(default)]
+    pub top_level: bool,
+
+    /// If true, bindings which are declared but never used are removed.
+    #[serde(default)]
+    pub unused: bool,
+
+    /// If true, function declarations are kept even if they are not used.
+    ///
+    /// This is useful if functions are looked up by name, e.g. from a stack
+    /// trace.
+    #[serde(default)]
+    pub keep_fn_names: bool,
+
+    /// If true, property accesses are assumed to be free of side effects, so
+    /// unused ones can be removed.
+    ///
+    /// Getters can have side effects, so this is off by default.
+    #[serde(default)]
+    pub pure_getters: bool,
+
+    /// If true, import specifiers which are never used are removed.
+    ///
+    /// Importing a module may have side effects, so an import whose
+    /// specifiers are all removed is kept as `import 'foo'`, unless
+    /// `drop_empty_imports` is also set.
+    #[serde(default)]
+    pub unused_imports: bool,
+
+    /// If true, imports whose specifiers are all removed by `unused_imports`
+    /// are removed entirely.
+    #[serde(default)]
+    pub drop_empty_imports: bool,
 }
 
 #[derive(Debug, Default)]
-struct Remover {
+struct Remover<'a> {
+    config: Config,
     normal_block: bool,
+    /// True if the statement list being folded is the body of a function or a
+    /// program, which may start with directives.
+    fn_body: bool,
+    /// Label of the statement being folded, if it's the body of a labeled
+    /// statement.
+    label: Option<JsWord>,
+    /// Usage information of the function being folded.
+    scope: Scope,
+    /// Used to find `/*#__PURE__*/` annotations.
+    comments: Option<&'a Comments>,
+    /// True if a `try` statement of the current function is being folded.
+    in_try: bool,
+}
+
+impl<'a> Remover<'a> {
+    /// Folds a node which creates a new variable scope, using usage
+    /// information of its body.
+    fn fold_scope<T>(&mut self, node: T) -> T
+    where
+        T: FoldWith<Self>
+            + for<'any> VisitWith<var::VarCollector<'any>>
+            + VisitWith<usage::UsageCounter>,
+    {
+        let scope = if self.config.unused {
+            Scope::analyze(&node)
+        } else {
+            Default::default()
+        };
+
+        let old = replace(&mut self.scope, scope);
+        let node = self.fold_body(node);
+        self.scope = old;
+
+        node
+    }
+
+    /// Folds a function or a program.
+    fn fold_body<T: FoldWith<Self>>(&mut self, node: T) -> T {
+        let old = replace(&mut self.fn_body, true);
+        let old_in_try = replace(&mut self.in_try, false);
+        let node = node.fold_children(self);
+        self.fn_body = old;
+        self.in_try = old_in_try;
+
+        node
+    }
+}
+
+impl<'a> Fold<Module> for Remover<'a> {
+    fn fold(&mut self, m: Module) -> Module {
+        let mut m = if self.config.top_level {
+            self.fold_scope(m)
+        } else {
+            self.fold_body(m)
+        };
+
+        if self.config.unused_imports {
+            self.drop_unused_imports(&mut m);
+        }
+
+        m
+    }
+}
+
+impl<'a> Fold<Script> for Remover<'a> {
+    fn fold(&mut self, s: Script) -> Script {
+        if self.config.top_level {
+            self.fold_scope(s)
+        } else {
+            self.fold_body(s)
+        }
+    }
+}
+
+impl<'a> Fold<Function> for Remover<'a> {
+    fn fold(&mut self, f: Function) -> Function {
+        self.fold_scope(f)
+    }
+}
+
+impl<'a> Fold<ArrowExpr> for Remover<'a> {
+    fn fold(&mut self, f: ArrowExpr) -> ArrowExpr {
+        self.fold_scope(f)
+    }
+}
+
+impl<'a> Fold<Constructor> for Remover<'a> {
+    fn fold(&mut self, c: Constructor) -> Constructor {
+        self.fold_scope(c)
+    }
+}
+
+impl<'a> Fold<GetterProp> for Remover<'a> {
+    fn fold(&mut self, p: GetterProp) -> GetterProp {
+        self.fold_scope(p)
+    }
 }
 
-impl<T: StmtLike> Fold<Vec<T>> for Remover
+impl<'a> Fold<SetterProp> for Remover<'a> {
+    fn fold(&mut self, p: SetterProp) -> SetterProp {
+        self.fold_scope(p)
+    }
+}
+
+impl<'a, T: StmtLike> Fold<Vec<T>> for Remover<'a>
 where
     Self: Fold<T>,
     T: VisitWith<Hoister>,
 {
-    fn fold(&mut self, stmts: Vec<T>) -> Vec<T> {
+    fn fold(&mut self, mut stmts: Vec<T>) -> Vec<T> {
         let is_block_stmt = self.normal_block;
         self.normal_block = false;
+        let is_fn_body = replace(&mut self.fn_body, false);
 
         let mut buf = Vec::with_capacity(stmts.len());
 
+        if is_fn_body {
+            // Directives are kept as-is.
+            let cnt = stmts.iter().take_while(|s| is_directive(*s)).count();
+            buf.extend(stmts.drain(..cnt));
+        }
+
         let mut iter = stmts.into_iter();
         while let Some(stmt_like) = iter.next() {
             self.normal_block = true;
-            let stmt_like = self.fold(stmt_like);
+            let mut stmt_like = self.fold(stmt_like);
             self.normal_block = false;
 
+            // Statements which are not rewritten below are kept in place, without a
+            // round-trip through `try_into_stmt` / `from_stmt`.
+            match stmt_like.as_stmt_mut() {
+                Some(Stmt::Block(BlockStmt { stmts, .. }))
+                    if !stmts.is_empty() && !is_ok_to_inline_block(stmts) =>
+                {
+                    *stmts = replace(stmts, vec![]).fold_with(self);
+                    buf.push(stmt_like);
+                    continue;
+                }
+                Some(stmt) if !self.is_rewritten_in_list(stmt, is_block_stmt) => {
+                    buf.push(stmt_like);
+                    continue;
+                }
+                _ => {}
+            }
+
             let stmt_like = match stmt_like.try_into_stmt() {
                 Ok(stmt) => {
                     let stmt = match stmt {
@@ -50,60 +248,126 @@ where
                             ..
                         }) if is_block_stmt => continue,
 
+                        // `(a(), b());` -> `a(); b();`
+                        Stmt::Expr(ExprStmt {
+                            expr: box Expr::Seq(SeqExpr { exprs, .. }),
+                            ..
+                        }) => {
+                            buf.extend(exprs.into_iter().filter_map(|e| self.ignore_result(*e)).map(
+                                |e| {
+                                    T::from_stmt(Stmt::Expr(ExprStmt {
+                                        span: e.span(),
+                                        expr: box e,
+                                    }))
+                                },
+                            ));
+                            continue;
+                        }
+
                         // Control flow
                         Stmt::Throw(..)
                         | Stmt::Return { .. }
                         | Stmt::Continue { .. }
                         | Stmt::Break { .. } => {
-                            let decls: Vec<_> = iter
-                                .flat_map(|t| extract_var_ids(&t))
-                                .map(|i| VarDeclarator {
-                                    span: i.span,
-                                    name: Pat::Ident(i),
-                                    init: None,
-                                    definite: false,
-                                })
-                                .collect();
-                            if !decls.is_empty() {
-                                buf.push(T::from_stmt(Stmt::Decl(Decl::Var(VarDecl {
-                                    span: DUMMY_SP,
-                                    kind: VarDeclKind::Var,
-                                    decls,
-                                    declare: false,
-                                }))));
-                            }
+                            let (var, kept) = self.remove_unreachable(iter.by_ref());
+                            buf.extend(var);
+                            buf.push(T::from_stmt(stmt));
+                            buf.extend(kept);
 
-                            let stmt_like = T::from_stmt(stmt);
-                            buf.push(stmt_like);
-
-                            return buf;
+                            break;
                         }
 
-                        Stmt::Block(BlockStmt { span, stmts, .. }) => {
-                            if stmts.len() == 0 {
-                                continue;
+                        // Blocks which cannot be inlined are handled above.
+                        Stmt::Block(BlockStmt { stmts, .. }) => {
+                            // Statements after a block which ends with a jump are never
+                            // executed.
+                            let has_jump = stmts.iter().any(|s| match s {
+                                Stmt::Throw(..)
+                                | Stmt::Return(..)
+                                | Stmt::Continue(..)
+                                | Stmt::Break(..) => true,
+                                _ => false,
+                            });
+                            let (var, kept) = if has_jump {
+                                self.remove_unreachable(iter.by_ref())
+                            } else {
+                                (None, vec![])
+                            };
+
+                            buf.extend(var);
+                            buf.extend(
+                                stmts
+                                    .into_iter()
+                                    .filter(|s| match s {
+                                        Stmt::Empty(..) => false,
+                                        _ => true,
+                                    })
+                                    .map(T::from_stmt),
+                            );
+                            buf.extend(kept);
+
+                            if has_jump {
+                                break;
                             }
+                            continue;
+                        }
 
-                            if !is_ok_to_inline_block(&stmts) {
-                                BlockStmt {
-                                    span,
-                                    stmts: stmts.fold_with(self),
+                        Stmt::Decl(Decl::Var(var)) => {
+                            let VarDecl {
+                                span,
+                                kind,
+                                decls,
+                                declare,
+                            } = var;
+
+                            let mut kept = vec![];
+                            for decl in decls {
+                                let is_unused = match decl.name {
+                                    Pat::Ident(ref i) => self.scope.is_unused(i),
+                                    _ => false,
+                                };
+                                if !is_unused {
+                                    kept.push(decl);
+                                    continue;
                                 }
-                                .into()
-                            } else {
-                                buf.extend(
-                                    stmts
-                                        .into_iter()
-                                        .filter(|s| match s {
-                                            Stmt::Empty(..) => false,
-                                            _ => true,
-                                        })
-                                        .map(T::from_stmt),
-                                );
+
+                                // Preserve side effects of the initializer, in order.
+                                if let Some(expr) =
+                                    decl.init.and_then(|init| self.ignore_result(*init))
+                                {
+                                    if !kept.is_empty() {
+                                        buf.push(T::from_stmt(Stmt::Decl(Decl::Var(VarDecl {
+                                            span,
+                                            kind,
+                                            decls: replace(&mut kept, vec![]),
+                                            declare,
+                                        }))));
+                                    }
+                                    buf.push(T::from_stmt(Stmt::Expr(ExprStmt {
+                                        span: decl.span,
+                                        expr: box expr,
+                                    })));
+                                }
+                            }
+
+                            if kept.is_empty() {
                                 continue;
                             }
+
+                            Stmt::Decl(Decl::Var(VarDecl {
+                                span,
+                                kind,
+                                decls: kept,
+                                declare,
+                            }))
                         }
 
+                        Stmt::Decl(Decl::Class(ClassDecl {
+                            ref ident,
+                            ref class,
+                            declare: false,
+                        })) if self.scope.is_unused(ident) && is_pure_class(class) => continue,
+
                         // Optimize if statement.
                         Stmt::If(IfStmt {
                             test,
@@ -115,11 +379,12 @@ where
                             match test.as_bool() {
                                 (purity, Known(val)) => {
                                     if !purity.is_pure() {
-                                        let expr = ignore_result(*test);
+                                        let test_span = test.span();
+                                        let expr = self.ignore_result(*test);
 
                                         if let Some(expr) = expr {
                                             buf.push(T::from_stmt(Stmt::Expr(ExprStmt {
-                                                span: DUMMY_SP,
+                                                span: test_span,
                                                 expr: box expr,
                                             })));
                                         }
@@ -164,13 +429,234 @@ where
             buf.push(stmt_like);
         }
 
+        if self.config.unused && !self.config.keep_fn_names {
+            self.drop_unused_fns(&mut buf);
+        }
+
+        if self.config.unused {
+            buf = self.remove_dead_stores(buf);
+        }
+
+        buf
+    }
+}
+
+impl<'a> Remover<'a> {
+    /// Removes statements in `rest`, which follow a jump and are never
+    /// executed.
+    ///
+    /// Returns a declaration of variables declared by the removed statements,
+    /// if any, and statements which are kept.
+    fn remove_unreachable<T, I>(&mut self, rest: I) -> (Option<T>, Vec<T>)
+    where
+        Self: Fold<T>,
+        T: StmtLike + VisitWith<Hoister>,
+        I: Iterator<Item = T>,
+    {
+        // Function declarations are hoisted, and imports and exports are
+        // declarative, so they are kept as-is.
+        let mut kept = vec![];
+        let mut ids = vec![];
+        for t in rest {
+            match t.as_stmt() {
+                Some(Stmt::Decl(Decl::Fn(..))) | None => kept.push(self.fold(t)),
+                _ => ids.extend(extract_var_ids(&t)),
+            }
+        }
+
+        let decls: Vec<_> = ids
+            .into_iter()
+            .map(|i| VarDeclarator {
+                span: i.span,
+                name: Pat::Ident(i),
+                init: None,
+                definite: false,
+            })
+            .collect();
+        let var = if decls.is_empty() {
+            None
+        } else {
+            Some(T::from_stmt(Stmt::Decl(Decl::Var(VarDecl {
+                span: DUMMY_SP,
+                kind: VarDeclKind::Var,
+                decls,
+                declare: false,
+            }))))
+        };
+
+        (var, kept)
+    }
+
+    /// Removes function declarations in `stmts` which are not used by other
+    /// code, directly or indirectly.
+    ///
+    /// Functions referenced only by removed functions, including themselves,
+    /// are removed too.
+    fn drop_unused_fns<T: StmtLike>(&self, stmts: &mut Vec<T>) {
+        let decls: Vec<_> = stmts
+            .iter()
+            .filter_map(|s| match s.as_stmt() {
+                Some(Stmt::Decl(Decl::Fn(f))) if self.scope.ref_count(&id(&f.ident)).is_some() => {
+                    Some(f)
+                }
+                _ => None,
+            })
+            .collect();
+        if decls.is_empty() {
+            return;
+        }
+
+        let ids: Vec<Id> = decls.iter().map(|f| id(&f.ident)).collect();
+        // References to the functions from the body of each function.
+        let refs: Vec<_> = decls
+            .iter()
+            .map(|f| usage::count_refs(&f.function, &ids))
+            .collect();
+
+        // Functions used by other code.
+        let mut used = FxHashSet::default();
+        let mut queue = vec![];
+        for (i, f) in ids.iter().enumerate() {
+            let inner: usize = refs.iter().map(|r| r.get(f).cloned().unwrap_or(0)).sum();
+            let total = self.scope.ref_count(f).unwrap_or(0);
+            if total > inner && used.insert(f.clone()) {
+                queue.push(i);
+            }
+        }
+
+        while let Some(i) = queue.pop() {
+            for (j, f) in ids.iter().enumerate() {
+                if refs[i].get(f).cloned().unwrap_or(0) > 0 && used.insert(f.clone()) {
+                    queue.push(j);
+                }
+            }
+        }
+
+        stmts.retain(|s| match s.as_stmt() {
+            Some(Stmt::Decl(Decl::Fn(f))) => {
+                let i = id(&f.ident);
+                !ids.contains(&i) || used.contains(&i)
+            }
+            _ => true,
+        });
+    }
+
+    /// Removes assignments to variables which are overwritten by the next
+    /// statement without being read.
+    ///
+    /// `x = f(); x = g();` -> `f(); x = g();`
+    fn remove_dead_stores<T: StmtLike>(&self, stmts: Vec<T>) -> Vec<T> {
+        // If the next statement throws, the value can be read from `catch` or
+        // `finally`.
+        if self.in_try {
+            return stmts;
+        }
+
+        let mut buf = Vec::with_capacity(stmts.len());
+        let mut iter = stmts.into_iter().peekable();
+        while let Some(stmt) = iter.next() {
+            let is_dead = match (stmt.as_stmt(), iter.peek().and_then(|s| s.as_stmt())) {
+                (Some(stmt), Some(next)) => self.is_dead_store(stmt, next),
+                _ => false,
+            };
+            if !is_dead {
+                buf.push(stmt);
+                continue;
+            }
+
+            if let Ok(Stmt::Expr(ExprStmt {
+                span,
+                expr: box Expr::Assign(AssignExpr { right, .. }),
+            })) = stmt.try_into_stmt()
+            {
+                buf.extend(
+                    self.ignore_result(*right)
+                        .map(|e| T::from_stmt(Stmt::Expr(ExprStmt { span, expr: box e }))),
+                );
+            }
+        }
+
         buf
     }
+
+    /// Removes import specifiers of `m` which are not referenced.
+    ///
+    /// This runs after the module is folded, so references removed as dead
+    /// code are not counted.
+    fn drop_unused_imports(&self, m: &mut Module) {
+        let scope = Scope::analyze(&*m);
+        let drop_empty_imports = self.config.drop_empty_imports;
+
+        m.body = replace(&mut m.body, vec![]).move_flat_map(|item| match item {
+            // `import 'foo'` is kept as-is.
+            ModuleItem::ModuleDecl(ModuleDecl::Import(mut import))
+                if !import.specifiers.is_empty() =>
+            {
+                import.specifiers.retain(|s| {
+                    let local = match s {
+                        ImportSpecifier::Specific(ImportSpecific { local, .. })
+                        | ImportSpecifier::Default(ImportDefault { local, .. })
+                        | ImportSpecifier::Namespace(ImportStarAs { local, .. }) => local,
+                    };
+                    !scope.is_unused(local)
+                });
+
+                if import.specifiers.is_empty() && drop_empty_imports {
+                    None
+                } else {
+                    Some(ModuleItem::ModuleDecl(ModuleDecl::Import(import)))
+                }
+            }
+            _ => Some(item),
+        });
+    }
+
+    /// Returns true if `stmt` assigns to a variable which is assigned again
+    /// by `next` before being read.
+    fn is_dead_store(&self, stmt: &Stmt, next: &Stmt) -> bool {
+        let (left, (next_left, next_right)) = match (assigned_var(stmt), assigned_var(next)) {
+            (Some((left, _)), Some(next)) => (id(left), next),
+            _ => return false,
+        };
+
+        // Variables captured by closures may be read by any function call.
+        if left != id(next_left) || !self.scope.is_local(&left) {
+            return false;
+        }
+
+        let refs = usage::count_refs(next_right, &[left.clone()]);
+        refs.get(&left).cloned().unwrap_or(0) == 0
+    }
+}
+
+/// Returns the variable and the value if `s` is `x = value;`.
+fn assigned_var(s: &Stmt) -> Option<(&Ident, &Expr)> {
+    match *s {
+        Stmt::Expr(ExprStmt {
+            expr:
+                box Expr::Assign(AssignExpr {
+                    op: op!("="),
+                    left: PatOrExpr::Pat(box Pat::Ident(ref left)),
+                    ref right,
+                    ..
+                }),
+            ..
+        }) => Some((left, &**right)),
+        _ => None,
+    }
 }
 
-impl Fold<Stmt> for Remover {
+impl<'a> Fold<Stmt> for Remover<'a> {
     fn fold(&mut self, stmt: Stmt) -> Stmt {
-        let stmt = stmt.fold_children(self);
+        let enclosing_label = self.label.take();
+        let stmt = match stmt {
+            Stmt::Labeled(LabeledStmt { span, label, body }) => {
+                self.label = Some(label.sym.clone());
+                let body = body.fold_with(self);
+                Stmt::Labeled(LabeledStmt { span, label, body })
+            }
+            _ => stmt.fold_children(self),
+        };
 
         match stmt {
             Stmt::If(IfStmt {
@@ -184,7 +670,7 @@ impl Fold<Stmt> for Remover {
                         return IfStmt {
                             test,
                             cons: box Stmt::Block(BlockStmt {
-                                span: DUMMY_SP,
+                                span: cons.span(),
                                 stmts: vec![*cons],
                             }),
                             alt,
@@ -199,8 +685,12 @@ impl Fold<Stmt> for Remover {
                 if let (p, Known(v)) = test.as_bool() {
                     // Preserve effect of the test
                     if !p.is_pure() {
-                        match ignore_result(*test).map(Box::new) {
-                            Some(expr) => stmts.push(Stmt::Expr(ExprStmt { span, expr })),
+                        let test_span = test.span();
+                        match self.ignore_result(*test).map(Box::new) {
+                            Some(expr) => stmts.push(Stmt::Expr(ExprStmt {
+                                span: test_span,
+                                expr,
+                            })),
                             None => {}
                         }
                     }
@@ -228,6 +718,23 @@ impl Fold<Stmt> for Remover {
                     return Stmt::Block(BlockStmt { span, stmts }).fold_with(self);
                 }
 
+                // `if (a()) f(); else f();` -> `a(); f();`
+                match alt {
+                    Some(ref alt) if !has_top_level_decl(&cons) && eq_ignore_span(&cons, alt) => {
+                        let test_span = test.span();
+                        if let Some(expr) = self.ignore_result(*test) {
+                            stmts.push(Stmt::Expr(ExprStmt {
+                                span: test_span,
+                                expr: box expr,
+                            }));
+                        }
+                        stmts.push(*cons);
+
+                        return Stmt::Block(BlockStmt { span, stmts }).fold_with(self);
+                    }
+                    _ => {}
+                }
+
                 let alt = match alt {
                     Some(box Stmt::Empty(..)) => None,
                     _ => alt,
@@ -235,7 +742,7 @@ impl Fold<Stmt> for Remover {
                 if alt.is_none() {
                     match *cons {
                         Stmt::Empty(..) => {
-                            if let Some(expr) = ignore_result(*test) {
+                            if let Some(expr) = self.ignore_result(*test) {
                                 return Stmt::Expr(ExprStmt {
                                     span,
                                     expr: box expr,
@@ -260,6 +767,10 @@ impl Fold<Stmt> for Remover {
                 Stmt::Empty(EmptyStmt { span: v.span })
             }
 
+            Stmt::Debugger(DebuggerStmt { span }) if self.config.drop_debugger => {
+                Stmt::Empty(EmptyStmt { span })
+            }
+
             Stmt::Labeled(LabeledStmt {
                 span,
                 body: box Stmt::Empty(..),
@@ -276,14 +787,24 @@ impl Fold<Stmt> for Remover {
                 ..
             }) if label.sym == b.sym => Stmt::Empty(EmptyStmt { span }),
 
+            // Remove unused label.
+            Stmt::Labeled(LabeledStmt { span, label, body }) => match *body {
+                // `l: function f() {}` is valid, but `function f() {}` may not be.
+                Stmt::Decl(Decl::Fn(..)) => Stmt::Labeled(LabeledStmt { span, label, body }),
+                _ if is_label_used(&label.sym, &body) => {
+                    Stmt::Labeled(LabeledStmt { span, label, body })
+                }
+                _ => *body,
+            },
+
             // `1;` -> `;`
             Stmt::Expr(ExprStmt {
                 span,
                 expr: box expr,
                 ..
-            }) => match ignore_result(expr) {
+            }) => match self.ignore_result(expr) {
                 Some(e) => Stmt::Expr(ExprStmt { span, expr: box e }),
-                None => Stmt::Empty(EmptyStmt { span: DUMMY_SP }),
+                None => Stmt::Empty(EmptyStmt { span }),
             },
 
             Stmt::Block(BlockStmt { span, stmts }) => {
@@ -324,6 +845,17 @@ impl Fold<Stmt> for Remover {
                     return Stmt::Block(block);
                 }
 
+                // Catch block is unreachable if the try block cannot throw.
+                if block.stmts.iter().all(|s| !can_throw(s)) {
+                    let var = handler.and_then(|h| Stmt::from(h.body).extract_var_ids_as_var());
+
+                    let mut stmts = block.stmts;
+                    stmts.extend(var.map(Decl::from).map(Stmt::from));
+                    stmts.extend(finalizer.map(Stmt::Block));
+
+                    return Stmt::Block(BlockStmt { span, stmts }).fold_with(self);
+                }
+
                 Stmt::Try(TryStmt {
                     span,
                     block,
@@ -351,7 +883,7 @@ impl Fold<Stmt> for Remover {
                                     },
                                 )) => {
                                     return Some(Stmt::Decl(Decl::Var(VarDecl {
-                                        span: DUMMY_SP,
+                                        span: var.span,
                                         kind: VarDeclKind::Var,
                                         decls: var
                                             .decls
@@ -369,6 +901,13 @@ impl Fold<Stmt> for Remover {
                                 done = true;
                                 None
                             }
+                            // `break` targeting the switch itself.
+                            Stmt::Break(BreakStmt {
+                                label: Some(ref l), ..
+                            }) if enclosing_label.as_ref() == Some(&l.sym) => {
+                                done = true;
+                                None
+                            }
                             Stmt::Return(..) | Stmt::Throw(..) => {
                                 done = true;
                                 Some(s)
@@ -391,9 +930,37 @@ impl Fold<Stmt> for Remover {
                     _ => false,
                 };
 
+                // An empty default does nothing, unless it falls through into
+                // another case.
+                if let Some(i) = s.cases.iter().position(|case| case.test.is_none()) {
+                    let next = s.cases[i + 1..].iter().find(|case| !case.cons.is_empty());
+                    let falls_through = match next {
+                        Some(case) => match case.cons[0] {
+                            Stmt::Break(BreakStmt { label: None, .. }) => false,
+                            _ => true,
+                        },
+                        None => false,
+                    };
+
+                    if s.cases[i].cons.is_empty() && !falls_through {
+                        s.cases.remove(i);
+                    }
+                }
+
+                // Trailing empty cases do nothing even if matched, unless there's a
+                // default which would be selected instead. Tests with side effects
+                // are kept, as they are evaluated only if previous tests fail.
+                if s.cases.iter().all(|case| case.test.is_some()) {
+                    while s.cases.last().map_or(false, |case| {
+                        case.cons.is_empty() && self.is_pure(case.test.as_ref().unwrap())
+                    }) {
+                        s.cases.pop();
+                    }
+                }
+
                 // Remove empty switch
                 if s.cases.is_empty() {
-                    match ignore_result(*s.discriminant) {
+                    match self.ignore_result(*s.discriminant) {
                         Some(expr) => {
                             return Stmt::Expr(ExprStmt {
                                 span: s.span,
@@ -410,7 +977,7 @@ impl Fold<Stmt> for Remover {
                     && !has_conditional_stopper(&s.cases[0].cons)
                 {
                     let mut stmts = remove_break(s.cases.remove(0).cons);
-                    if let Some(expr) = ignore_result(*s.discriminant) {
+                    if let Some(expr) = self.ignore_result(*s.discriminant) {
                         prepend(&mut stmts, expr.into_stmt());
                     }
 
@@ -469,7 +1036,16 @@ impl Fold<Stmt> for Remover {
 
                 let mut var_ids = vec![];
                 if let Some(i) = selected {
-                    if !has_conditional_stopper(&s.cases[i].cons) {
+                    // The selected case and the cases it falls through into.
+                    let end = s.cases[i..]
+                        .iter()
+                        .position(|case| has_unconditional_stopper(&case.cons))
+                        .map_or(s.cases.len(), |j| i + j + 1);
+                    let has_stopper = s.cases[i..end]
+                        .iter()
+                        .any(|case| has_conditional_stopper(&case.cons));
+
+                    if !has_stopper {
                         let mut stmts = s.cases.remove(i).cons;
                         let mut cases = s.cases.drain(i..);
 
@@ -489,7 +1065,7 @@ impl Fold<Stmt> for Remover {
                             .flat_map(|case| case.cons)
                             .flat_map(|stmt| stmt.extract_var_ids())
                             .map(|i| VarDeclarator {
-                                span: DUMMY_SP,
+                                span: i.span,
                                 name: Pat::Ident(i),
                                 init: None,
                                 definite: false,
@@ -516,7 +1092,8 @@ impl Fold<Stmt> for Remover {
                     }
                 } else {
                     match *s.discriminant {
-                        Expr::Lit(..) => {
+                        // A case with a non-constant test may match, or have side effects.
+                        Expr::Lit(..) if non_constant_case_idx.is_none() => {
                             let idx = s.cases.iter().position(|v| v.test.is_none());
                             if let Some(i) = idx {
                                 if !has_conditional_stopper(&s.cases[i].cons) {
@@ -592,12 +1169,23 @@ impl Fold<Stmt> for Remover {
                         .iter()
                         .all(|case| case.test.is_none() || case.cons.is_empty());
 
+                    // Tests are evaluated until one of them matches, so we can't know
+                    // which of them are evaluated.
+                    let has_impure_test = s.cases.iter().any(|case| match case.test {
+                        Some(ref test) => !self.is_pure(test),
+                        None => false,
+                    });
+
                     if is_default_last
                         && is_all_case_empty
+                        && !has_impure_test
                         && !has_conditional_stopper(&s.cases.last().unwrap().cons)
                     {
                         let stmts = s.cases.pop().unwrap().cons;
-                        let stmts = remove_break(stmts);
+                        let mut stmts = remove_break(stmts);
+                        if let Some(expr) = self.ignore_result(*s.discriminant) {
+                            prepend(&mut stmts, expr.into_stmt());
+                        }
                         return Stmt::Block(BlockStmt {
                             span: s.span,
                             stmts,
@@ -634,7 +1222,7 @@ impl Fold<Stmt> for Remover {
                             .collect();
                         if !decls.is_empty() {
                             return Stmt::Decl(Decl::Var(VarDecl {
-                                span: DUMMY_SP,
+                                span: s.span,
                                 kind: VarDeclKind::Var,
                                 decls,
                                 declare: false,
@@ -673,9 +1261,34 @@ impl Fold<Stmt> for Remover {
                 }
             }
 
+            Stmt::For(s)
+                if s.test.is_none() && is_executed_once(&s.body, enclosing_label.as_ref()) =>
+            {
+                let init = s.init.map(|init| match init {
+                    VarDeclOrExpr::VarDecl(var) => Stmt::Decl(Decl::Var(var)),
+                    VarDeclOrExpr::Expr(expr) => Stmt::Expr(ExprStmt {
+                        span: expr.span(),
+                        expr,
+                    }),
+                });
+
+                Stmt::Block(BlockStmt {
+                    span: s.span,
+                    stmts: init
+                        .into_iter()
+                        .chain(once(prepare_loop_body_for_inlining(*s.body)))
+                        .collect(),
+                })
+                .fold_with(self)
+            }
+
             Stmt::While(s) => {
                 if let (purity, Known(v)) = s.test.as_bool() {
                     if v {
+                        if purity.is_pure() && is_executed_once(&s.body, enclosing_label.as_ref()) {
+                            return prepare_loop_body_for_inlining(*s.body).fold_with(self);
+                        }
+
                         Stmt::While(WhileStmt {
                             test: box Expr::Lit(Lit::Bool(Bool {
                                 span: s.test.span(),
@@ -710,17 +1323,23 @@ impl Fold<Stmt> for Remover {
                             body: s.body,
                         })
                     } else {
-                        if let Some(test) = ignore_result(*s.test) {
+                        let body = prepare_loop_body_for_inlining(*s.body);
+                        // `break` and `continue` cannot be retargeted.
+                        if has_jump_to_loop(&body, enclosing_label.as_ref()) {
+                            return Stmt::DoWhile(DoWhileStmt {
+                                body: box body,
+                                ..s
+                            });
+                        }
+
+                        if let Some(test) = self.ignore_result(*s.test) {
                             BlockStmt {
                                 span: s.span,
-                                stmts: vec![
-                                    prepare_loop_body_for_inlining(*s.body).fold_with(self),
-                                    test.into_stmt(),
-                                ],
+                                stmts: vec![body.fold_with(self), test.into_stmt()],
                             }
                             .into()
                         } else {
-                            prepare_loop_body_for_inlining(*s.body).fold_with(self)
+                            body.fold_with(self)
                         }
                     }
                 } else {
@@ -728,6 +1347,21 @@ impl Fold<Stmt> for Remover {
                 }
             }
 
+            Stmt::ForIn(s) if is_empty_collection(&s.right) => {
+                remove_loop(s.span, &s.left, &s.body)
+            }
+
+            // Iterating over an object literal throws, and `for await` waits
+            // even if there's no element.
+            Stmt::ForOf(s) => match *s.right {
+                Expr::Array(ArrayLit { ref elems, .. })
+                    if elems.is_empty() && s.await_token.is_none() =>
+                {
+                    remove_loop(s.span, &s.left, &s.body)
+                }
+                _ => Stmt::ForOf(s),
+            },
+
             Stmt::Decl(Decl::Var(v)) => {
                 let decls = v.decls.move_flat_map(|v| {
                     if !is_literal(&v.init) {
@@ -759,7 +1393,83 @@ impl Fold<Stmt> for Remover {
     }
 }
 
-impl Fold<Pat> for Remover {
+/// Returns true if `s` is a string literal statement, which is a directive if
+/// it's at the start of a function or a program.
+fn is_directive<T: StmtLike>(s: &T) -> bool {
+    match s.as_stmt() {
+        Some(&Stmt::Expr(ExprStmt {
+            expr: box Expr::Lit(Lit::Str(..)),
+            ..
+        })) => true,
+        _ => false,
+    }
+}
+
+/// Returns true if `s` is a declaration or a block containing a declaration.
+fn has_top_level_decl(s: &Stmt) -> bool {
+    match s {
+        Stmt::Decl(..) => true,
+        Stmt::Block(BlockStmt { stmts, .. }) => stmts.iter().any(|s| match s {
+            Stmt::Decl(..) => true,
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+/// Returns true if `a` and `b` are equal, ignoring positions in the source.
+fn eq_ignore_span(a: &Stmt, b: &Stmt) -> bool {
+    struct SpanRemover;
+
+    impl Fold<Span> for SpanRemover {
+        fn fold(&mut self, span: Span) -> Span {
+            // Syntax context is preserved, as it's a part of identifiers.
+            DUMMY_SP.with_ctxt(span.ctxt())
+        }
+    }
+
+    a.clone().fold_with(&mut SpanRemover) == b.clone().fold_with(&mut SpanRemover)
+}
+
+/// Returns true if `e` is `{}` or `[]`.
+fn is_empty_collection(e: &Expr) -> bool {
+    match *e {
+        Expr::Object(ObjectLit { ref props, .. }) => props.is_empty(),
+        Expr::Array(ArrayLit { ref elems, .. }) => elems.is_empty(),
+        _ => false,
+    }
+}
+
+/// Replaces a loop which is never executed with declarations of variables it
+/// declares.
+fn remove_loop(span: Span, left: &VarDeclOrPat, body: &Stmt) -> Stmt {
+    let mut ids = match *left {
+        VarDeclOrPat::VarDecl(ref v) => extract_var_ids(v),
+        VarDeclOrPat::Pat(..) => vec![],
+    };
+    ids.extend(body.extract_var_ids());
+
+    if ids.is_empty() {
+        return Stmt::Empty(EmptyStmt { span });
+    }
+
+    Stmt::Decl(Decl::Var(VarDecl {
+        span,
+        kind: VarDeclKind::Var,
+        declare: false,
+        decls: ids
+            .into_iter()
+            .map(|i| VarDeclarator {
+                span: i.span,
+                name: Pat::Ident(i),
+                init: None,
+                definite: false,
+            })
+            .collect(),
+    }))
+}
+
+impl<'a> Fold<Pat> for Remover<'a> {
     fn fold(&mut self, p: Pat) -> Pat {
         let p = p.fold_children(self);
 
@@ -794,7 +1504,7 @@ impl Fold<Pat> for Remover {
     }
 }
 
-impl Fold<ArrayPat> for Remover {
+impl<'a> Fold<ArrayPat> for Remover<'a> {
     fn fold(&mut self, p: ArrayPat) -> ArrayPat {
         let mut p: ArrayPat = p.fold_children(self);
 
@@ -820,7 +1530,7 @@ impl Fold<ArrayPat> for Remover {
     }
 }
 
-impl Fold<ObjectPat> for Remover {
+impl<'a> Fold<ObjectPat> for Remover<'a> {
     fn fold(&mut self, p: ObjectPat) -> ObjectPat {
         let mut p = p.fold_children(self);
 
@@ -858,7 +1568,7 @@ impl Fold<ObjectPat> for Remover {
     }
 }
 
-impl Fold<ObjectPatProp> for Remover {
+impl<'a> Fold<ObjectPatProp> for Remover<'a> {
     fn fold(&mut self, p: ObjectPatProp) -> ObjectPatProp {
         let p = p.fold_children(self);
 
@@ -891,7 +1601,17 @@ impl Fold<ObjectPatProp> for Remover {
     }
 }
 
-impl Fold<SwitchStmt> for Remover {
+impl<'a> Fold<TryStmt> for Remover<'a> {
+    fn fold(&mut self, s: TryStmt) -> TryStmt {
+        let old = replace(&mut self.in_try, true);
+        let s = s.fold_children(self);
+        self.in_try = old;
+
+        s
+    }
+}
+
+impl<'a> Fold<SwitchStmt> for Remover<'a> {
     fn fold(&mut self, s: SwitchStmt) -> SwitchStmt {
         let s: SwitchStmt = s.fold_children(self);
 
@@ -912,7 +1632,7 @@ impl Fold<SwitchStmt> for Remover {
     }
 }
 
-impl Fold<SeqExpr> for Remover {
+impl<'a> Fold<SeqExpr> for Remover<'a> {
     fn fold(&mut self, e: SeqExpr) -> SeqExpr {
         let mut e: SeqExpr = e.fold_children(self);
         if e.exprs.is_empty() {
@@ -920,14 +1640,14 @@ impl Fold<SeqExpr> for Remover {
         }
 
         let last = e.exprs.pop().unwrap();
-        let mut exprs = e.exprs.move_flat_map(|e| ignore_result(*e).map(Box::new));
+        let mut exprs = e.exprs.move_flat_map(|e| self.ignore_result(*e).map(Box::new));
         exprs.push(last);
 
         SeqExpr { exprs, ..e }
     }
 }
 
-impl Fold<Expr> for Remover {
+impl<'a> Fold<Expr> for Remover<'a> {
     fn fold(&mut self, e: Expr) -> Expr {
         let e: Expr = e.fold_children(self);
 
@@ -981,6 +1701,36 @@ impl Fold<Expr> for Remover {
                 return *e.cons
             }
 
+            Expr::Cond(CondExpr {
+                span,
+                test,
+                cons,
+                alt,
+            }) => {
+                if let (purity, Known(v)) = test.as_bool() {
+                    let expr = if v { cons } else { alt };
+                    if purity.is_pure() {
+                        return *expr;
+                    }
+
+                    // Preserve effect of the test
+                    return match self.ignore_result(*test) {
+                        Some(test) => Expr::Seq(SeqExpr {
+                            span,
+                            exprs: vec![box test, expr],
+                        }),
+                        None => *expr,
+                    };
+                }
+
+                return Expr::Cond(CondExpr {
+                    span,
+                    test,
+                    cons,
+                    alt,
+                });
+            }
+
             _ => {}
         }
 
@@ -988,16 +1738,19 @@ impl Fold<Expr> for Remover {
     }
 }
 
-impl Fold<ForStmt> for Remover {
+impl<'a> Fold<ForStmt> for Remover<'a> {
     fn fold(&mut self, s: ForStmt) -> ForStmt {
         let s = s.fold_children(self);
 
         ForStmt {
             init: s.init.and_then(|e| match e {
-                VarDeclOrExpr::Expr(e) => ignore_result(*e).map(Box::new).map(VarDeclOrExpr::from),
+                VarDeclOrExpr::Expr(e) => self
+                    .ignore_result(*e)
+                    .map(Box::new)
+                    .map(VarDeclOrExpr::from),
                 _ => Some(e),
             }),
-            update: s.update.and_then(|e| ignore_result(*e).map(Box::new)),
+            update: s.update.and_then(|e| self.ignore_result(*e).map(Box::new)),
             test: s.test.and_then(|e| {
                 let span = e.span();
                 if let Known(value) = e.as_pure_bool() {
@@ -1015,259 +1768,479 @@ impl Fold<ForStmt> for Remover {
     }
 }
 
-/// Ignores the result.
-///
-/// Returns
-///  - [Some] if `e` has a side effect.
-///  - [None] if `e` does not have a side effect.
-#[inline(never)]
-fn ignore_result(e: Expr) -> Option<Expr> {
-    match e {
-        Expr::Lit(Lit::Num(..))
-        | Expr::Lit(Lit::Bool(..))
-        | Expr::Lit(Lit::Null(..))
-        | Expr::Lit(Lit::Regex(..))
-        | Expr::Ident(..) => None,
-
-        Expr::Lit(Lit::Str(ref v)) if v.value.is_empty() => None,
-
-        Expr::Paren(ParenExpr { expr, .. }) => ignore_result(*expr),
-
-        Expr::Assign(AssignExpr {
-            op: op!("="),
-            left: PatOrExpr::Pat(box Pat::Ident(ref l)),
-            right: box Expr::Ident(r),
-            ..
-        }) if l.sym == r.sym && l.span.ctxt() == r.span.ctxt() => None,
-
-        Expr::Bin(BinExpr {
-            span,
-            left,
-            op,
-            right,
-        }) if op != op!("&&") && op != op!("||") => {
-            let left = ignore_result(*left);
-            let right = ignore_result(*right);
-
-            match (left, right) {
-                (Some(l), Some(r)) => {
-                    ignore_result(preserve_effects(span, *undefined(span), vec![box l, box r]))
-                }
-                (Some(l), None) => Some(l),
-                (None, Some(r)) => Some(r),
-                (None, None) => None,
-            }
+impl<'a> Remover<'a> {
+    /// Returns true if the call or new expression at `span` is annotated with
+    /// `/*#__PURE__*/` or `/*@__PURE__*/`.
+    fn is_pure_annotated(&self, span: Span) -> bool {
+        let comments = match self.comments {
+            Some(comments) => comments,
+            None => return false,
+        };
+
+        match comments.leading_comments(span.lo()) {
+            Some(cmts) => cmts.iter().any(|c| {
+                c.kind == CommentKind::Block
+                    && (c.text.contains("#__PURE__") || c.text.contains("@__PURE__"))
+            }),
+            None => false,
         }
+    }
 
-        Expr::Bin(BinExpr {
-            span,
-            left,
-            op,
-            right,
-        }) => {
-            if op == op!("&&") {
-                let right = if let Some(right) = ignore_result(*right) {
-                    box right
-                } else {
-                    return ignore_result(*left);
+    /// Returns true if evaluating `e` has no side effects.
+    ///
+    /// Unlike [ExprExt::may_have_side_effects], this checks arguments of calls
+    /// to pure functions.
+    fn is_pure(&self, e: &Expr) -> bool {
+        self.ignore_result(e.clone()).is_none()
+    }
+
+    /// Returns true if calling `e` does not have any side effect, like
+    ///
+    /// ```js
+    /// (function () {
+    ///     var x = 1;
+    ///     return x;
+    /// })
+    /// ```
+    fn is_pure_fn_expr(&self, e: &Expr) -> bool {
+        fn is_pure_params(params: &[Pat]) -> bool {
+            params.iter().all(|p| match p {
+                Pat::Ident(..) => true,
+                _ => false,
+            })
+        }
+
+        match e {
+            Expr::Paren(ParenExpr { expr, .. }) => self.is_pure_fn_expr(expr),
+
+            Expr::Fn(FnExpr {
+                function:
+                    Function {
+                        params,
+                        body: Some(BlockStmt { stmts, .. }),
+                        is_async: false,
+                        is_generator: false,
+                        ..
+                    },
+                ..
+            }) => is_pure_params(params) && stmts.iter().all(|s| self.is_pure_stmt(s)),
+
+            Expr::Arrow(ArrowExpr {
+                params,
+                body,
+                is_async: false,
+                is_generator: false,
+                ..
+            }) => {
+                is_pure_params(params)
+                    && match body {
+                        BlockStmtOrExpr::BlockStmt(BlockStmt { stmts, .. }) => {
+                            stmts.iter().all(|s| self.is_pure_stmt(s))
+                        }
+                        BlockStmtOrExpr::Expr(expr) => self.is_pure(expr),
+                    }
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Returns true if `s` in the body of a function does not have any side
+    /// effect.
+    fn is_pure_stmt(&self, s: &Stmt) -> bool {
+        match s {
+            Stmt::Empty(..) | Stmt::Decl(Decl::Fn(..)) => true,
+            Stmt::Expr(ExprStmt { expr, .. }) => self.is_pure(expr),
+            Stmt::Return(ReturnStmt { arg, .. }) => match arg {
+                Some(arg) => self.is_pure(arg),
+                None => true,
+            },
+            Stmt::Decl(Decl::Var(v)) => v.decls.iter().all(|d| {
+                let is_ident = match d.name {
+                    Pat::Ident(..) => true,
+                    _ => false,
                 };
 
-                let l = left.as_pure_bool();
+                is_ident
+                    && match d.init {
+                        Some(ref init) => self.is_pure(init),
+                        None => true,
+                    }
+            }),
+            _ => false,
+        }
+    }
 
-                if let Known(l) = l {
-                    Some(Expr::Lit(Lit::Bool(Bool { span, value: l })))
+    /// Ignores the result.
+    ///
+    /// Returns
+    ///  - [Some] if `e` has a side effect.
+    ///  - [None] if `e` does not have a side effect.
+    #[inline(never)]
+    fn ignore_result(&self, e: Expr) -> Option<Expr> {
+        match e {
+            Expr::Lit(Lit::Num(..))
+            | Expr::Lit(Lit::Bool(..))
+            | Expr::Lit(Lit::Null(..))
+            | Expr::Lit(Lit::Regex(..))
+            | Expr::Ident(..) => None,
+
+            Expr::Lit(Lit::Str(ref v)) if v.value.is_empty() => None,
+
+            Expr::Member(MemberExpr {
+                span,
+                obj: ExprOrSuper::Expr(obj),
+                prop,
+                computed,
+            }) if self.config.pure_getters => {
+                let obj = self.ignore_result(*obj);
+                let prop = if computed {
+                    self.ignore_result(*prop)
                 } else {
-                    Some(Expr::Bin(BinExpr {
+                    None
+                };
+
+                match (obj, prop) {
+                    (Some(o), Some(p)) => self.ignore_result(preserve_effects(
                         span,
-                        left,
-                        op,
-                        right,
-                    }))
+                        *undefined(span),
+                        vec![box o, box p],
+                    )),
+                    (Some(o), None) => Some(o),
+                    (None, Some(p)) => Some(p),
+                    (None, None) => None,
+                }
+            }
+
+            Expr::Paren(ParenExpr { expr, .. }) => self.ignore_result(*expr),
+
+            // Suspension points are observable.
+            Expr::Await(..) | Expr::Yield(..) => Some(e),
+
+            Expr::OptChain(OptChainExpr { span, expr }) => match *expr {
+                // Computed key is evaluated only if the object is not nullish.
+                Expr::Member(MemberExpr {
+                    obj: ExprOrSuper::Expr(obj),
+                    prop,
+                    computed,
+                    ..
+                }) if self.config.pure_getters && (!computed || !prop.may_have_side_effects()) => {
+                    self.ignore_result(*obj)
+                }
+
+                // Known functions are never nullish, so the call is always made.
+                Expr::Call(call)
+                    if match call.callee {
+                        ExprOrSuper::Expr(ref callee) => is_known_fn(callee),
+                        _ => false,
+                    } =>
+                {
+                    self.ignore_result(Expr::Call(call))
                 }
-            } else {
-                debug_assert_eq!(op, op!("||"));
 
-                let l = left.as_pure_bool();
+                expr => Some(Expr::OptChain(OptChainExpr {
+                    span,
+                    expr: box expr,
+                })),
+            },
+
+            Expr::Assign(AssignExpr {
+                op: op!("="),
+                left: PatOrExpr::Pat(box Pat::Ident(ref l)),
+                right: box Expr::Ident(r),
+                ..
+            }) if l.sym == r.sym && l.span.ctxt() == r.span.ctxt() => None,
+
+            // `in` throws if the right operand is not an object.
+            Expr::Bin(BinExpr {
+                op: op!("in"),
+                ref right,
+                ..
+            }) if match **right {
+                Expr::Object(..) | Expr::Array(..) | Expr::Fn(..) | Expr::Class(..) => false,
+                _ => true,
+            } =>
+            {
+                Some(e)
+            }
+
+            // `instanceof` throws if the right operand is not a constructor.
+            Expr::Bin(BinExpr {
+                op: op!("instanceof"),
+                ref right,
+                ..
+            }) if match **right {
+                Expr::Fn(..) | Expr::Class(..) => false,
+                _ => true,
+            } =>
+            {
+                Some(e)
+            }
 
-                if let Known(l) = l {
-                    if l {
-                        None
+            Expr::Bin(BinExpr {
+                span,
+                left,
+                op,
+                right,
+            }) if op != op!("&&") && op != op!("||") => {
+                let left = self.ignore_result(*left);
+                let right = self.ignore_result(*right);
+
+                match (left, right) {
+                    (Some(l), Some(r)) => self.ignore_result(preserve_effects(
+                        span,
+                        *undefined(span),
+                        vec![box l, box r],
+                    )),
+                    (Some(l), None) => Some(l),
+                    (None, Some(r)) => Some(r),
+                    (None, None) => None,
+                }
+            }
+
+            Expr::Bin(BinExpr {
+                span,
+                left,
+                op,
+                right,
+            }) => {
+                if op == op!("&&") {
+                    let right = if let Some(right) = self.ignore_result(*right) {
+                        box right
+                    } else {
+                        return self.ignore_result(*left);
+                    };
+
+                    let l = left.as_pure_bool();
+
+                    if let Known(l) = l {
+                        Some(Expr::Lit(Lit::Bool(Bool { span, value: l })))
                     } else {
-                        ignore_result(*right)
-                    }
-                } else {
-                    let right = ignore_result(*right);
-                    if let Some(right) = right {
                         Some(Expr::Bin(BinExpr {
                             span,
                             left,
                             op,
-                            right: box right,
+                            right,
                         }))
+                    }
+                } else {
+                    debug_assert_eq!(op, op!("||"));
+
+                    let l = left.as_pure_bool();
+
+                    if let Known(l) = l {
+                        if l {
+                            None
+                        } else {
+                            self.ignore_result(*right)
+                        }
                     } else {
-                        ignore_result(*left)
+                        let right = self.ignore_result(*right);
+                        if let Some(right) = right {
+                            Some(Expr::Bin(BinExpr {
+                                span,
+                                left,
+                                op,
+                                right: box right,
+                            }))
+                        } else {
+                            self.ignore_result(*left)
+                        }
                     }
                 }
             }
-        }
 
-        Expr::Unary(UnaryExpr { span, op, arg }) => match op {
-            op!("void")
-            | op!("typeof")
-            | op!(unary, "+")
-            | op!(unary, "-")
-            | op!("!")
-            | op!("~") => ignore_result(*arg),
-            _ => Some(Expr::Unary(UnaryExpr { span, op, arg })),
-        },
+            Expr::Unary(UnaryExpr { span, op, arg }) => match op {
+                op!("void")
+                | op!("typeof")
+                | op!(unary, "+")
+                | op!(unary, "-")
+                | op!("!")
+                | op!("~") => self.ignore_result(*arg),
+                _ => Some(Expr::Unary(UnaryExpr { span, op, arg })),
+            },
 
-        Expr::Array(ArrayLit { span, elems, .. }) => {
-            let mut has_spread = false;
-            let elems = elems.move_flat_map(|v| match v {
-                Some(ExprOrSpread {
-                    spread: Some(..), ..
-                }) => {
-                    has_spread = true;
-                    Some(v)
-                }
-                None => None,
-                Some(ExprOrSpread { spread: None, expr }) => ignore_result(*expr).map(|expr| {
+            Expr::Array(ArrayLit { span, elems, .. }) => {
+                let mut has_spread = false;
+                let elems = elems.move_flat_map(|v| match v {
                     Some(ExprOrSpread {
-                        spread: None,
-                        expr: box expr,
-                    })
-                }),
-            });
+                        spread: Some(..), ..
+                    }) => {
+                        has_spread = true;
+                        Some(v)
+                    }
+                    None => None,
+                    Some(ExprOrSpread { spread: None, expr }) => {
+                        self.ignore_result(*expr).map(|expr| {
+                            Some(ExprOrSpread {
+                                spread: None,
+                                expr: box expr,
+                            })
+                        })
+                    }
+                });
 
-            if elems.is_empty() {
-                None
-            } else {
-                if has_spread {
-                    Some(Expr::Array(ArrayLit { span, elems }))
+                if elems.is_empty() {
+                    None
                 } else {
-                    ignore_result(preserve_effects(
+                    if has_spread {
+                        Some(Expr::Array(ArrayLit { span, elems }))
+                    } else {
+                        self.ignore_result(preserve_effects(
+                            span,
+                            *undefined(span),
+                            elems.into_iter().map(|v| v.unwrap().expr),
+                        ))
+                    }
+                }
+            }
+
+            Expr::Object(ObjectLit { span, props, .. }) => {
+                let props = props.move_flat_map(|v| match v {
+                    PropOrSpread::Spread(..) => Some(v),
+                    PropOrSpread::Prop(ref p) => {
+                        if is_literal(&p) {
+                            None
+                        } else {
+                            Some(v)
+                        }
+                    }
+                });
+
+                if props.is_empty() {
+                    None
+                } else {
+                    self.ignore_result(preserve_effects(
                         span,
                         *undefined(span),
-                        elems.into_iter().map(|v| v.unwrap().expr),
+                        once(box Expr::Object(ObjectLit { span, props })),
                     ))
                 }
             }
-        }
 
-        Expr::Object(ObjectLit { span, props, .. }) => {
-            let props = props.move_flat_map(|v| match v {
-                PropOrSpread::Spread(..) => Some(v),
-                PropOrSpread::Prop(ref p) => {
-                    if is_literal(&p) {
-                        None
-                    } else {
-                        Some(v)
-                    }
-                }
-            });
+            Expr::New(NewExpr {
+                span,
+                ref callee,
+                args,
+                ..
+            }) if callee.is_pure_callee() || self.is_pure_annotated(span) => {
+                self.ignore_result(Expr::Array(ArrayLit {
+                    span,
+                    elems: args
+                        .map(|args| args.into_iter().map(Some).collect())
+                        .unwrap_or_else(Default::default),
+                }))
+            }
 
-            if props.is_empty() {
-                None
-            } else {
-                ignore_result(preserve_effects(
+            Expr::Call(CallExpr {
+                span,
+                callee: ExprOrSuper::Expr(ref callee),
+                args,
+                ..
+            }) if callee.is_pure_callee()
+                || self.is_pure_fn_expr(callee)
+                || self.is_pure_annotated(span) =>
+            {
+                self.ignore_result(Expr::Array(ArrayLit {
                     span,
-                    *undefined(DUMMY_SP),
-                    once(box Expr::Object(ObjectLit { span, props })),
-                ))
+                    elems: args.into_iter().map(Some).collect(),
+                }))
             }
-        }
 
-        Expr::New(NewExpr {
-            span,
-            ref callee,
-            args,
-            ..
-        }) if callee.is_pure_callee() => ignore_result(Expr::Array(ArrayLit {
-            span,
-            elems: args
-                .map(|args| args.into_iter().map(Some).collect())
-                .unwrap_or_else(Default::default),
-        })),
-
-        Expr::Call(CallExpr {
-            span,
-            callee: ExprOrSuper::Expr(ref callee),
-            args,
-            ..
-        }) if callee.is_pure_callee() => ignore_result(Expr::Array(ArrayLit {
-            span,
-            elems: args.into_iter().map(Some).collect(),
-        })),
+            Expr::Tpl(Tpl {
+                span,
+                exprs,
+                quasis,
+            }) => {
+                if exprs.iter().all(|e| match **e {
+                    Expr::Lit(..) | Expr::Ident(..) => true,
+                    _ => false,
+                }) {
+                    return None;
+                }
 
-        Expr::Tpl(Tpl { span, exprs, .. }) => {
-            ignore_result(preserve_effects(span, *undefined(span), exprs))
-        }
+                // Interpolation converts values to string, which may call `toString` of an
+                // object.
+                if exprs.iter().all(|e| !e.may_have_side_effects()) {
+                    return Some(Expr::Tpl(Tpl {
+                        span,
+                        exprs,
+                        quasis,
+                    }));
+                }
 
-        Expr::TaggedTpl(TaggedTpl {
-            span, tag, exprs, ..
-        }) if tag.is_pure_callee() => {
-            ignore_result(preserve_effects(span, *undefined(span), exprs))
-        }
+                self.ignore_result(preserve_effects(span, *undefined(span), exprs))
+            }
 
-        //
-        // Function expressions are useless if they are not used.
-        //
-        // As function expressions cannot start with 'function',
-        // this will be reached only if other things
-        // are removed while folding children.
-        Expr::Fn(..) => None,
-
-        Expr::Seq(SeqExpr {
-            span, mut exprs, ..
-        }) => {
-            if exprs.is_empty() {
-                return None;
+            Expr::TaggedTpl(TaggedTpl {
+                span, tag, exprs, ..
+            }) if tag.is_pure_callee() => {
+                self.ignore_result(preserve_effects(span, *undefined(span), exprs))
             }
 
-            let last = ignore_result(*exprs.pop().unwrap()).map(Box::new);
+            //
+            // Function expressions are useless if they are not used.
+            //
+            // As function expressions cannot start with 'function',
+            // this will be reached only if other things
+            // are removed while folding children.
+            Expr::Fn(..) => None,
 
-            exprs.extend(last);
+            // Same for arrow functions, including async ones.
+            Expr::Arrow(..) => None,
 
-            Some(Expr::Seq(SeqExpr { span, exprs }))
-        }
+            Expr::Seq(SeqExpr {
+                span, mut exprs, ..
+            }) => {
+                if exprs.is_empty() {
+                    return None;
+                }
 
-        Expr::Cond(CondExpr {
-            span,
-            test,
-            cons,
-            alt,
-        }) => {
-            let alt = if let Some(alt) = ignore_result(*alt) {
-                alt
-            } else {
-                return ignore_result(Expr::Bin(BinExpr {
-                    span,
-                    left: test,
-                    op: op!("&&"),
-                    right: cons,
-                }));
-            };
+                let last = self.ignore_result(*exprs.pop().unwrap()).map(Box::new);
 
-            let cons = if let Some(cons) = ignore_result(*cons) {
-                cons
-            } else {
-                return ignore_result(Expr::Bin(BinExpr {
-                    span,
-                    left: test,
-                    op: op!("||"),
-                    right: box alt,
-                }));
-            };
+                exprs.extend(last);
 
-            Some(Expr::Cond(CondExpr {
+                Some(Expr::Seq(SeqExpr { span, exprs }))
+            }
+
+            Expr::Cond(CondExpr {
                 span,
                 test,
-                cons: box cons,
-                alt: box alt,
-            }))
-        }
+                cons,
+                alt,
+            }) => {
+                let alt = if let Some(alt) = self.ignore_result(*alt) {
+                    alt
+                } else {
+                    return self.ignore_result(Expr::Bin(BinExpr {
+                        span,
+                        left: test,
+                        op: op!("&&"),
+                        right: cons,
+                    }));
+                };
+
+                let cons = if let Some(cons) = self.ignore_result(*cons) {
+                    cons
+                } else {
+                    return self.ignore_result(Expr::Bin(BinExpr {
+                        span,
+                        left: test,
+                        op: op!("||"),
+                        right: box alt,
+                    }));
+                };
 
-        _ => Some(e),
+                Some(Expr::Cond(CondExpr {
+                    span,
+                    test,
+                    cons: box cons,
+                    alt: box alt,
+                }))
+            }
+
+            _ => Some(e),
+        }
     }
 }
 
@@ -1339,6 +2312,114 @@ fn is_ok_to_inline_block(s: &[Stmt]) -> bool {
     }
 }
 
+impl<'a> Remover<'a> {
+    /// Returns true if `s` is removed or rewritten by `Fold<Vec<T>>`.
+    fn is_rewritten_in_list(&self, s: &Stmt, is_block_stmt: bool) -> bool {
+        match s {
+            Stmt::Empty(..)
+            | Stmt::Throw(..)
+            | Stmt::Return(..)
+            | Stmt::Continue(..)
+            | Stmt::Break(..)
+            | Stmt::Block(..)
+            | Stmt::If(..) => true,
+            Stmt::Expr(ExprStmt {
+                expr: box Expr::Lit(..),
+                ..
+            }) => is_block_stmt,
+            Stmt::Expr(ExprStmt {
+                expr: box Expr::Seq(..),
+                ..
+            }) => true,
+            Stmt::Decl(Decl::Var(VarDecl { declare: false, .. })) => self.config.unused,
+            Stmt::Decl(Decl::Class(ClassDecl { declare: false, .. })) => self.config.unused,
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if `e` is a pure builtin function which is always defined.
+///
+/// [ExprExt::is_pure_callee] accepts any member of `Math`, including
+/// undefined ones.
+fn is_known_fn(e: &Expr) -> bool {
+    match *e {
+        Expr::Ident(ref i) => i.sym == js_word!("Date"),
+        Expr::Member(MemberExpr {
+            obj: ExprOrSuper::Expr(ref obj),
+            ref prop,
+            computed: false,
+            ..
+        }) if obj.is_ident_ref_to(js_word!("Math")) => match **prop {
+            Expr::Ident(ref prop) => match &*prop.sym {
+                "abs" | "acos" | "acosh" | "asin" | "asinh" | "atan" | "atan2" | "atanh"
+                | "cbrt" | "ceil" | "clz32" | "cos" | "cosh" | "exp" | "expm1" | "floor"
+                | "fround" | "hypot" | "imul" | "log" | "log10" | "log1p" | "log2" | "max"
+                | "min" | "pow" | "random" | "round" | "sign" | "sin" | "sinh" | "sqrt"
+                | "tan" | "tanh" | "trunc" => true,
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true if evaluating the class definition `c` has no side effects.
+///
+/// Static property initializers and computed keys are evaluated when the
+/// class is defined, so only literals are allowed there. A superclass may
+/// throw if it's not a constructor, so classes with one are not pure.
+fn is_pure_class(c: &Class) -> bool {
+    let is_pure_key = |key: &PropName| match key {
+        PropName::Computed(ComputedPropName { expr, .. }) => is_literal(expr),
+        _ => true,
+    };
+
+    c.decorators.is_empty()
+        && c.super_class.is_none()
+        && c.body.iter().all(|m| match m {
+            ClassMember::Constructor(ctor) => ctor.params.iter().all(|p| match p {
+                PatOrTsParamProp::TsParamProp(p) => p.decorators.is_empty(),
+                PatOrTsParamProp::Pat(..) => true,
+            }),
+            ClassMember::Method(m) => m.function.decorators.is_empty() && is_pure_key(&m.key),
+            ClassMember::PrivateMethod(m) => m.function.decorators.is_empty(),
+            ClassMember::ClassProp(p) => {
+                p.decorators.is_empty()
+                    && (!p.computed || is_literal(&p.key))
+                    && (!p.is_static || is_literal(&p.value))
+            }
+            ClassMember::PrivateProp(p) => {
+                p.decorators.is_empty() && (!p.is_static || is_literal(&p.value))
+            }
+            ClassMember::TsIndexSignature(..) => true,
+        })
+}
+
+/// Returns true if `s` may throw, or may jump out of a `try` block.
+///
+/// This is conservative. Only literals and declarations initialized with
+/// literals are considered as non-throwing.
+fn can_throw(s: &Stmt) -> bool {
+    match s {
+        Stmt::Empty(..) | Stmt::Decl(Decl::Fn(..)) => false,
+        Stmt::Expr(ExprStmt { expr, .. }) => match **expr {
+            Expr::Lit(..) => false,
+            _ => true,
+        },
+        Stmt::Decl(Decl::Var(v)) => v.decls.iter().any(|d| {
+            let is_ident = match d.name {
+                Pat::Ident(..) => true,
+                _ => false,
+            };
+
+            !is_ident || !is_literal(&d.init)
+        }),
+        _ => true,
+    }
+}
+
 fn is_block_scoped_stuff(s: &Stmt) -> bool {
     match s {
         Stmt::Decl(Decl::Var(VarDecl { kind, .. }))
@@ -1383,6 +2464,162 @@ fn prepare_loop_body_for_inlining(stmt: Stmt) -> Stmt {
     BlockStmt { span, stmts }.into()
 }
 
+/// Returns true if `body` contains `break` or `continue` targeting `label`.
+fn is_label_used(label: &JsWord, body: &Stmt) -> bool {
+    struct Visitor<'a> {
+        label: &'a JsWord,
+        found: bool,
+    }
+
+    // Labels are not visible from functions.
+    impl<'a> Visit<Function> for Visitor<'a> {
+        fn visit(&mut self, _: &Function) {}
+    }
+
+    impl<'a> Visit<ArrowExpr> for Visitor<'a> {
+        fn visit(&mut self, _: &ArrowExpr) {}
+    }
+
+    impl<'a> Visit<BreakStmt> for Visitor<'a> {
+        fn visit(&mut self, s: &BreakStmt) {
+            if let Some(ref l) = s.label {
+                self.found |= l.sym == *self.label;
+            }
+        }
+    }
+
+    impl<'a> Visit<ContinueStmt> for Visitor<'a> {
+        fn visit(&mut self, s: &ContinueStmt) {
+            if let Some(ref l) = s.label {
+                self.found |= l.sym == *self.label;
+            }
+        }
+    }
+
+    let mut v = Visitor {
+        label,
+        found: false,
+    };
+    body.visit_with(&mut v);
+    v.found
+}
+
+/// Returns true if a loop body ends with an unlabeled `break` and nothing else
+/// jumps to the loop, which means that the body is executed at most once.
+fn is_executed_once(body: &Stmt, label: Option<&JsWord>) -> bool {
+    match *body {
+        Stmt::Break(BreakStmt { label: None, .. }) => true,
+        Stmt::Block(BlockStmt { ref stmts, .. }) => match stmts.split_last() {
+            Some((Stmt::Break(BreakStmt { label: None, .. }), rest)) => {
+                !rest.iter().any(|s| has_jump_to_loop(s, label))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true if `body` contains `break` or `continue` targeting the loop
+/// whose body is `body`.
+///
+/// `label` is the label of the loop.
+fn has_jump_to_loop(body: &Stmt, label: Option<&JsWord>) -> bool {
+    struct Visitor<'a> {
+        label: Option<&'a JsWord>,
+        /// True inside a nested switch or loop.
+        in_breakable: bool,
+        /// True inside a nested loop.
+        in_loop: bool,
+        found: bool,
+    }
+
+    impl<'a> Visitor<'a> {
+        fn visit_loop<T: VisitWith<Self>>(&mut self, node: &T) {
+            let old_breakable = replace(&mut self.in_breakable, true);
+            let old_loop = replace(&mut self.in_loop, true);
+            node.visit_children(self);
+            self.in_breakable = old_breakable;
+            self.in_loop = old_loop;
+        }
+
+        fn is_target(&self, label: &Option<Ident>, is_nested: bool) -> bool {
+            match *label {
+                Some(ref l) => Some(&l.sym) == self.label,
+                None => !is_nested,
+            }
+        }
+    }
+
+    impl<'a> Visit<SwitchStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &SwitchStmt) {
+            node.discriminant.visit_with(self);
+
+            let old = replace(&mut self.in_breakable, true);
+            node.cases.visit_with(self);
+            self.in_breakable = old;
+        }
+    }
+
+    impl<'a> Visit<ForStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &ForStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl<'a> Visit<ForInStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &ForInStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl<'a> Visit<ForOfStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &ForOfStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl<'a> Visit<WhileStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &WhileStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl<'a> Visit<DoWhileStmt> for Visitor<'a> {
+        fn visit(&mut self, node: &DoWhileStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl<'a> Visit<Function> for Visitor<'a> {
+        fn visit(&mut self, _: &Function) {}
+    }
+
+    impl<'a> Visit<ArrowExpr> for Visitor<'a> {
+        fn visit(&mut self, _: &ArrowExpr) {}
+    }
+
+    impl<'a> Visit<BreakStmt> for Visitor<'a> {
+        fn visit(&mut self, s: &BreakStmt) {
+            self.found |= self.is_target(&s.label, self.in_breakable);
+        }
+    }
+
+    impl<'a> Visit<ContinueStmt> for Visitor<'a> {
+        fn visit(&mut self, s: &ContinueStmt) {
+            self.found |= self.is_target(&s.label, self.in_loop);
+        }
+    }
+
+    let mut v = Visitor {
+        label,
+        in_breakable: false,
+        in_loop: false,
+        found: false,
+    };
+    body.visit_with(&mut v);
+    v.found
+}
+
 fn has_unconditional_stopper(s: &[Stmt]) -> bool {
     check_for_stopper(s, false)
 }
@@ -1395,6 +2632,64 @@ fn check_for_stopper(s: &[Stmt], only_conditional: bool) -> bool {
     struct Visitor {
         in_cond: bool,
         found: bool,
+        /// True inside a nested switch or loop, which is the target of
+        /// unlabeled `break`.
+        in_breakable: bool,
+        /// True inside a nested loop, which is the target of unlabeled
+        /// `continue`.
+        in_loop: bool,
+    }
+
+    impl Visitor {
+        fn visit_loop<T: VisitWith<Self>>(&mut self, node: &T) {
+            let old_cond = replace(&mut self.in_cond, true);
+            let old_breakable = replace(&mut self.in_breakable, true);
+            let old_loop = replace(&mut self.in_loop, true);
+            node.visit_children(self);
+            self.in_cond = old_cond;
+            self.in_breakable = old_breakable;
+            self.in_loop = old_loop;
+        }
+    }
+
+    impl Visit<SwitchStmt> for Visitor {
+        fn visit(&mut self, node: &SwitchStmt) {
+            node.discriminant.visit_with(self);
+
+            let old = replace(&mut self.in_breakable, true);
+            node.cases.visit_with(self);
+            self.in_breakable = old;
+        }
+    }
+
+    impl Visit<ForStmt> for Visitor {
+        fn visit(&mut self, node: &ForStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl Visit<ForInStmt> for Visitor {
+        fn visit(&mut self, node: &ForInStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl Visit<ForOfStmt> for Visitor {
+        fn visit(&mut self, node: &ForOfStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl Visit<WhileStmt> for Visitor {
+        fn visit(&mut self, node: &WhileStmt) {
+            self.visit_loop(node)
+        }
+    }
+
+    impl Visit<DoWhileStmt> for Visitor {
+        fn visit(&mut self, node: &DoWhileStmt) {
+            self.visit_loop(node)
+        }
     }
 
     impl Visit<Function> for Visitor {
@@ -1427,7 +2722,7 @@ fn check_for_stopper(s: &[Stmt], only_conditional: bool) -> bool {
 
     impl Visit<BreakStmt> for Visitor {
         fn visit(&mut self, s: &BreakStmt) {
-            if self.in_cond && s.label.is_none() {
+            if self.in_cond && s.label.is_none() && !self.in_breakable {
                 self.found = true
             }
         }
@@ -1435,7 +2730,7 @@ fn check_for_stopper(s: &[Stmt], only_conditional: bool) -> bool {
 
     impl Visit<ContinueStmt> for Visitor {
         fn visit(&mut self, s: &ContinueStmt) {
-            if self.in_cond && s.label.is_none() {
+            if self.in_cond && s.label.is_none() && !self.in_loop {
                 self.found = true
             }
         }
@@ -1460,6 +2755,8 @@ fn check_for_stopper(s: &[Stmt], only_conditional: bool) -> bool {
     let mut v = Visitor {
         in_cond: !only_conditional,
         found: false,
+        in_breakable: false,
+        in_loop: false,
     };
     s.visit_with(&mut v);
     v.found
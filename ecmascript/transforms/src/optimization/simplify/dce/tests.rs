@@ -1,6 +1,7 @@
-use super::dce;
-use crate::optimization::expr_simplifier;
-use swc_common::chain;
+use super::{dce, dce_with, dce_with_comments, Config};
+use crate::{fixer, optimization::expr_simplifier, tests::Tester};
+use ast::*;
+use swc_common::{chain, FoldWith, Spanned};
 
 macro_rules! test_stmt {
     ($l:expr, $r:expr) => {
@@ -25,6 +26,32 @@ fn test_same(s: &str) {
     test(s, s)
 }
 
+/// Tests `dce` alone, for code which `expr_simplifier` would fold first.
+fn test_dce_only(src: &str, expected: &str) {
+    test_transform!(::swc_ecma_parser::Syntax::default(), |_| dce(), src, expected)
+}
+
+fn test_with(config: Config, src: &str, expected: &str) {
+    test_transform!(
+        ::swc_ecma_parser::Syntax::default(),
+        |_| chain!(expr_simplifier(), dce_with(config)),
+        src,
+        expected
+    )
+}
+
+/// Tests with removal of unused variables enabled.
+fn test_unused(src: &str, expected: &str) {
+    test_with(
+        Config {
+            unused: true,
+            ..Default::default()
+        },
+        src,
+        expected,
+    )
+}
+
 // /// Should not modify expression.
 // macro_rules! same_stmt {
 //     ($l:expr) => {
@@ -62,8 +89,8 @@ fn test_remove_no_op_labelled_statement() {
         "a: { var x; break a; } x = 2; ",
     );
 
-    test("b: { var x = 1; } x = 2;", "b: var x = 1; x = 2;");
-    test("a: b: { var x = 1; } x = 2;", "a: b: var x = 1; x = 2;");
+    test("b: { var x = 1; } x = 2;", "var x = 1; x = 2;");
+    test("a: b: { var x = 1; } x = 2;", "var x = 1; x = 2;");
 }
 
 #[test]
@@ -103,9 +130,15 @@ fn test_fold_block_with_declaration() {
     test("{'hi'; let x;}", "{let x}");
     test("{x = 4; {let y}}", "x = 4; {let y}");
     test_same("{class C {}} {class C {}}");
-    test("{label: var x}", "label: var x");
+    test("{label: var x}", "var x");
     // `{label: let x}` is a syntax error
-    test_same("{label: var x; let y;}");
+    test("{label: var x; let y;}", "{var x; let y;}");
+    test_same("{function f() {}}");
+    test_same("if (x) {let y = 1}");
+    test_same("if (x) {class C {}}");
+    test_same("if (x) {function f() {}}");
+    test_same("{let x = 1} let x = 2;");
+    test("{a(); {const x = 1}} const x = 2;", "a(); {const x = 1} const x = 2;");
 }
 
 #[test]
@@ -159,8 +192,8 @@ fn test_hook() {
     test("a() ? b() : true", "a() && b()");
     test("a() ? true : b()", "a() || b()");
 
-    test("(a = true) ? b() : c()", "a = true, b()");
-    test("(a = false) ? b() : c()", "a = false, c()");
+    test("(a = true) ? b() : c()", "a = true; b();");
+    test("(a = false) ? b() : c()", "a = false; c();");
     test(
         "do {f()} while((a = true) ? b() : c())",
         "do f(); while(a = true , b())",
@@ -204,15 +237,15 @@ fn test_constant_condition_with_side_effect1() {
     // test("var b=/ab/;if(b)x=1;", "var b=/ab/;x=1");
     test_same("var b=f();if(b)x=1;");
     test_same("b=b++;if(b)x=b;");
-    test("(b=0,b=1);if(b)x=b;", "b=0,b=1;if(b)x=b;");
+    test("(b=0,b=1);if(b)x=b;", "b=0;b=1;if(b)x=b;");
     // test("b=1;if(foo,b)x=b;", "b=1;x=b;");
     test_same("b=1;if(foo=1,b)x=b;");
 }
 
 #[test]
 fn test_constant_condition_with_side_effect2() {
-    test("(b=true)?x=1:x=2;", "b=true,x=1");
-    test("(b=false)?x=1:x=2;", "b=false,x=2");
+    test("(b=true)?x=1:x=2;", "b=true;x=1");
+    test("(b=false)?x=1:x=2;", "b=false;x=2");
     test("if (b=/ab/) x=1;", "b=/ab/;x=1");
     // test("var b;b=/ab/;(b)?x=1:x=2;", "var b;b=/ab/;x=1");
     test_same("var b;b=f();b?x=1:x=2;");
@@ -269,10 +302,7 @@ fn test_fold_useless_do_2() {
 
 #[test]
 fn test_fold_useless_do_3() {
-    test(
-        "do { try { foo() } catch (e) { break; } } while (0);",
-        "try { foo(); } catch (e) { break; }",
-    );
+    test_same("do { try { foo() } catch (e) { break; } } while (0);");
     test("do { foo(); break; } while(0)", "foo();");
     test(
         "do { for (;;) {foo(); continue;} } while(0)",
@@ -280,7 +310,7 @@ fn test_fold_useless_do_3() {
     );
     test(
         "l1: do { for (;;) { foo() } } while(0)",
-        "l1: for(;;) foo();",
+        "for(;;) foo();",
     );
     test(
         "do { switch (1) { default: foo(); break} } while(0)",
@@ -293,7 +323,7 @@ fn test_fold_useless_do_3() {
 
     test(
         "l1: { do { x = 1; break l1; } while (0); x = 2; }",
-        "l1: { x = 1; break l1; x = 2;}",
+        "l1: { x = 1; break l1; }",
     );
 }
 
@@ -357,8 +387,8 @@ fn test_remove_useless_ops2() {
 
     // Known side-effect free functions calls are removed.
     test("Math.random()", "");
-    test("Math.random(f() + g())", "f(),g();");
-    test("Math.random(f(),g(),h())", "f(),g(),h();");
+    test("Math.random(f() + g())", "f();g();");
+    test("Math.random(f(),g(),h())", "f();g();h();");
 
     // Calls to functions with unknown side-effects are are left.
     test_same("f();");
@@ -380,7 +410,7 @@ fn test_remove_useless_ops2() {
     test("a=(+f(),g())", "a=(f(),g())");
     test("a=(true,g())", "a=g()");
     test("f(),true", "f()");
-    test("f() + g()", "f(),g()");
+    test("f() + g()", "f();g()");
 
     test("for(;;+f()){}", "for(;;f());");
     test("for(+f();;g()){}", "for(f();;g());");
@@ -502,7 +532,6 @@ fn test_optimize_switch_2() {
 }
 
 #[test]
-#[ignore]
 fn test_optimize_switch_3() {
     test(
         concat!(
@@ -608,7 +637,7 @@ fn test_optimize_switch_bug11536863() {
 fn test_optimize_switch2() {
     test(
         "outer: switch (2) {\n  case 2:\n    f();\n    break outer;\n}",
-        "outer: {f(); break outer;}",
+        "f();",
     );
 }
 
@@ -1021,7 +1050,7 @@ fn test_no_remove_call1() {
 
 #[test]
 fn test_no_remove_call2() {
-    test("a()+b()", "a(),b()");
+    test("a()+b()", "a();b()");
 }
 
 #[test]
@@ -1126,7 +1155,7 @@ fn test_hook10() {
     test("((function () {}), true) ? a() : b()", "a()");
     test(
         "((function () {alert(x)})(), true) ? a() : b()",
-        "(function(){alert(x)})(),a()",
+        "(function(){alert(x)})();a()",
     );
 }
 
@@ -1152,7 +1181,7 @@ fn test_short_circuit4() {
 
 #[test]
 fn test_complex1() {
-    test("1 && a() + b() + c()", "a(), b(), c()");
+    test("1 && a() + b() + c()", "a(); b(); c()");
 }
 
 #[test]
@@ -1317,7 +1346,7 @@ fn test_try_catch_finally() {
     test_same("try {foo()} catch (e) {bar()}");
     test_same("try { try {foo()} catch (e) {bar()}} catch (x) {bar()}");
     test("try {var x = 1} finally {}", "var x = 1;");
-    test_same("try {var x = 1} finally {x()}");
+    test("try {var x = 1} finally {x()}", "var x = 1; x();");
     test(
         "function f() { return; try{var x = 1}finally{} }",
         "function f() { var x; return; }",
@@ -1340,6 +1369,7 @@ fn test_object_literal() {
     test("({a:1})", "");
     test("({a:foo()})", "foo()");
     test("({'a':foo()})", "foo()");
+    test("({a: () => foo(), b: bar()})", "bar()");
     // Object-spread may tigger getters.
     test_same("({...a})");
     test_same("({...foo()})");
@@ -1351,6 +1381,7 @@ fn test_array_literal() {
     test("([1])", "");
     test("([a])", "");
     test("([foo()])", "foo()");
+    test("([() => foo(), async () => { await bar(); }])", "");
 }
 
 #[test]
@@ -1662,3 +1693,917 @@ fn test_function_call_references_setter_is_not_removed() {
         "foo();",
     ));
 }
+
+#[test]
+fn test_remove_unused_var() {
+    test_unused(
+        "function f() { var a = 1; var b = foo(); console.log(a); }",
+        "function f() { var a = 1; foo(); console.log(a); }",
+    );
+    test_unused(
+        "function f() { var a = 1, b = 2, c = a; return c; }",
+        "function f() { var a = 1, c = a; return c; }",
+    );
+    test_unused(
+        "function f() { var a = foo(), b = bar(), c = baz(); return b; }",
+        "function f() { foo(); var b = bar(); baz(); return b; }",
+    );
+    test_unused(
+        "function f() { let a = 1; const b = function () {}; }",
+        "function f() {}",
+    );
+}
+
+#[test]
+fn test_remove_unused_var_keeps_used() {
+    test_unused(
+        "function f() { var a = 1; return function () { return a; }; }",
+        "function f() { var a = 1; return function () { return a; }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; a = 2; }",
+        "function f() { var a = 1; a = 2; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return { a }; }",
+        "function f() { var a = 1; return { a }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return foo.a; }",
+        "function f() { return foo.a; }",
+    );
+    test_unused(
+        "function f() { var a = 1; eval('a'); }",
+        "function f() { var a = 1; eval('a'); }",
+    );
+    // `arguments[0]` is `x` in sloppy mode.
+    test_unused(
+        "function f(x) { var x = 2; return arguments[0]; }",
+        "function f(x) { var x = 2; return arguments[0]; }",
+    );
+}
+
+#[test]
+fn test_remove_unused_var_disabled_by_default() {
+    test_same("function f() { var a = 1; var b = foo(); console.log(a); }");
+}
+
+#[test]
+fn test_hoisted_decls_after_stopper() {
+    test(
+        "function f() { return g(); function g() { return 1; } var x = 2; }",
+        "function f() { var x; return g(); function g() { return 1; } }",
+    );
+    test(
+        "function f() { throw g(); foo(); function g() { var y = 1; } }",
+        "function f() { throw g(); function g() { var y = 1; } }",
+    );
+    test(
+        "function f() { while (a) { break; function g() {} } }",
+        "function f() { while (a) { break; function g() {} } }",
+    );
+}
+
+#[test]
+fn test_config_top_level() {
+    let src = "var a = 1; var b = foo(); console.log(a);";
+
+    // Top-level bindings may be used by other scripts.
+    test_same(src);
+    test_unused(src, src);
+
+    test_with(
+        Config {
+            top_level: true,
+            unused: true,
+            ..Default::default()
+        },
+        src,
+        "var a = 1; foo(); console.log(a);",
+    );
+}
+
+#[test]
+fn test_optimize_switch_with_own_label() {
+    test(
+        "outer: switch (1) { case 1: a(); break outer; case 2: b(); }",
+        "a();",
+    );
+    test(
+        "outer: switch (1) { case 1: a(); case 2: b(); break outer; default: c(); }",
+        "a(); b();",
+    );
+}
+
+#[test]
+fn test_optimize_switch_with_other_label() {
+    test(
+        "outer: { inner: switch (1) { case 1: a(); break outer; case 2: b(); } c(); }",
+        "outer: { a(); break outer; }",
+    );
+}
+
+#[test]
+fn test_optimize_switch_with_nested_switch() {
+    test(
+        concat!(
+            "outer: switch (1) {",
+            "  case 1:",
+            "    switch (x) { case 2: a(); break; }",
+            "    b();",
+            "    break outer;",
+            "  case 2: c();",
+            "}",
+        ),
+        "switch (x) { case 2: a(); break; } b();",
+    );
+}
+
+#[test]
+fn test_optimize_switch_with_impure_case_test() {
+    test_same("switch (1) { case sideEffect(): a(); case 1: b(); }");
+    test_same("switch (1) { case sideEffect(): a(); default: b(); }");
+    test_same("switch (x) { case sideEffect(): default: b(); }");
+    test_same("switch (x) { case Math.abs(f()): default: b(); }");
+    test(
+        "switch (f()) { case 1: case 2: default: b(); }",
+        "f(); b();",
+    );
+}
+
+#[test]
+fn test_optimize_switch_with_fallthrough() {
+    test(
+        "switch (1) { case 1: a(); case 2: b(); break; case 3: c(); }",
+        "a(); b();",
+    );
+    test(
+        "switch (1) { case 1: a(); default: b(); case 3: c(); break; case 4: d(); }",
+        "a(); b(); c();",
+    );
+    test(
+        "switch (1) { case 2: a(); default: b(); case 1: c(); case 3: d(); }",
+        "c(); d();",
+    );
+    test(
+        "function f() { switch (1) { case 1: a(); case 2: return b(); case 3: c(); } }",
+        "function f() { a(); return b(); }",
+    );
+
+    // `break` in a case reached by fall-through must stay in the switch.
+    test_same("switch (1) { case 1: a(); case 2: if (x) break; b(); }");
+    test("switch (1) { case 1: a(); break; case 2: if (x) break; b(); }", "a();");
+}
+
+#[test]
+fn test_fold_if_with_impure_known_test() {
+    test("if (x = f(), true) { a() } else { b() }", "x = f(); a();");
+    test("if (x = f(), false) { a() } else { b() }", "x = f(); b();");
+    test("if (x = f(), false) { a() }", "x = f();");
+    test("if ([f()]) { a() } else { var y = b() }", "f(); var y; a();");
+    test("if (x = 0) { var y = a() } else { b() }", "x = 0; var y; b();");
+}
+
+fn test_pure_getters(src: &str, expected: &str) {
+    test_with(
+        Config {
+            pure_getters: true,
+            ..Default::default()
+        },
+        src,
+        expected,
+    )
+}
+
+#[test]
+fn test_pure_getters_member() {
+    test_same("a.b.c;");
+    test_same("arr.length;");
+    test_same("a[b];");
+
+    test_pure_getters("a.b.c;", "");
+    test_pure_getters("arr.length;", "");
+    test_pure_getters("a[b];", "");
+    test_pure_getters("a[f()];", "f();");
+    test_pure_getters("f().b;", "f();");
+    test_pure_getters("f()[g()];", "f(); g();");
+}
+
+#[test]
+fn test_remove_unused_template() {
+    test("`foo ${bar} baz ${1}`;", "");
+    test("`foo ${bar()} baz`;", "bar();");
+    test("`foo ${bar()} ${a} ${baz()}`;", "bar(); baz();");
+    test_same("`foo ${{ toString: f }}`;");
+}
+
+fn test_ts_with(config: Config, src: &str, expected: &str) {
+    test_transform!(
+        ::swc_ecma_parser::Syntax::Typescript(Default::default()),
+        |_| chain!(expr_simplifier(), dce_with(config)),
+        src,
+        expected
+    )
+}
+
+#[test]
+fn test_opt_chain() {
+    let pure_getters = Config {
+        pure_getters: true,
+        ..Default::default()
+    };
+
+    test_ts_with(Default::default(), "a?.b;", "a?.b;");
+    test_ts_with(Default::default(), "a?.b?.c;", "a?.b?.c;");
+    test_ts_with(Default::default(), "a?.();", "a?.();");
+    test_ts_with(Default::default(), "Math.sin?.(f());", "f();");
+    test_ts_with(Default::default(), "Math.foo?.(f());", "Math.foo?.(f());");
+
+    test_ts_with(pure_getters, "a?.b;", "");
+    test_ts_with(pure_getters, "a?.[b];", "");
+    test_ts_with(pure_getters, "a?.[f()];", "a?.[f()];");
+    test_ts_with(pure_getters, "(x(), a)?.b;", "x();");
+    test_ts_with(pure_getters, "a?.b?.c;", "");
+    test_ts_with(pure_getters, "f()?.b?.c;", "f();");
+    test_ts_with(pure_getters, "if (a?.b) {}", "");
+    test_ts_with(Default::default(), "if (a?.b) {}", "a?.b;");
+}
+
+#[test]
+fn test_keep_await_and_yield() {
+    test(
+        "async function f() { [await p]; }",
+        "async function f() { await p; }",
+    );
+    test(
+        "async function f() { void await p; }",
+        "async function f() { await p; }",
+    );
+    test(
+        "async function f() { (await p) + 1; }",
+        "async function f() { await p; }",
+    );
+    test(
+        "async function f() { (await p, 1); }",
+        "async function f() { await p; }",
+    );
+    test("function* f() { [yield]; }", "function* f() { yield; }");
+    test("function* f() { void (yield a); }", "function* f() { yield a; }");
+}
+
+#[test]
+fn test_keep_throwing_in_and_instanceof() {
+    test_same("'x' in null;");
+    test_same("'x' in 1;");
+    test_same("a in b;");
+    test_same("x instanceof 5;");
+    test_same("x instanceof Foo;");
+    test_same("x instanceof {};");
+
+    test("'x' in {};", "");
+    test("'x' in [];", "");
+    test("x instanceof function () {};", "");
+    test("x instanceof class {};", "");
+    test("f() in {};", "f();");
+}
+
+#[test]
+fn test_keep_module_decls_after_stopper() {
+    test_same("throw new Error(); import './x'; export const a = 1;");
+    test(
+        "throw new Error(); foo(); import './x'; var b = 2; export { b }; export default a;",
+        "var b; throw new Error(); import './x'; export { b }; export default a;",
+    );
+}
+
+#[test]
+fn test_remove_unused_fn() {
+    test_unused(
+        concat!(
+            "function main() {",
+            "  function a() { return 1; }",
+            "  function b() { return 2; }",
+            "  function c() { return b(); }",
+            "  return a();",
+            "}",
+        ),
+        "function main() { function a() { return 1; } return a(); }",
+    );
+
+    // Mutual recursion
+    test_unused(
+        concat!(
+            "function main() {",
+            "  function a() { return b(); }",
+            "  function b() { return a(); }",
+            "  function c() { return c(); }",
+            "}",
+        ),
+        "function main() {}",
+    );
+
+    // Used only from a used function
+    test_unused(
+        concat!(
+            "function main() {",
+            "  function a() { return b(); }",
+            "  function b() { return 1; }",
+            "  return a;",
+            "}",
+        ),
+        concat!(
+            "function main() {",
+            "  function a() { return b(); }",
+            "  function b() { return 1; }",
+            "  return a;",
+            "}",
+        ),
+    );
+}
+
+#[test]
+fn test_remove_unused_fn_top_level() {
+    let config = Config {
+        top_level: true,
+        unused: true,
+        ..Default::default()
+    };
+
+    test_with(
+        config,
+        "function a() {} function b() {} function c() {} c();",
+        "function c() {} c();",
+    );
+    test_with(
+        config,
+        "function a() {} function b() {} export { b };",
+        "function b() {} export { b };",
+    );
+    test_unused(
+        "function a() {} function b() {}",
+        "function a() {} function b() {}",
+    );
+}
+
+#[test]
+fn test_keep_unused_fn() {
+    test_unused(
+        "function main() { function a() {} eval('a()'); }",
+        "function main() { function a() {} eval('a()'); }",
+    );
+    test_with(
+        Config {
+            unused: true,
+            keep_fn_names: true,
+            ..Default::default()
+        },
+        "function main() { function a() {} }",
+        "function main() { function a() {} }",
+    );
+}
+
+#[test]
+fn test_remove_pure_iife() {
+    test("(function () { var x = 1; })();", "");
+    test("(function () { var x = 1; return x; })();", "");
+    test("(function (a) { return a; })(1);", "");
+    test("(() => { let x = 1; })();", "");
+    test("(() => 1)();", "");
+    test("(function () { return 1; })(f());", "f();");
+
+    test_same("(function () { x = 1; })();");
+    test_same("(function () { this.x = 1; })();");
+    test_same("(function () { return f(); })();");
+    test_same("(function (a = f()) {})();");
+    test_same("(() => f())();");
+
+    // Arguments of calls to pure functions may have side effects.
+    test("(function () { Math.abs(f()); })();", "(function () { f(); })();");
+    test_same("(function () { return Math.abs(f()); })();");
+    test_same("(function () { var x = Math.abs(f()); })();");
+    test_same("(() => Math.abs(f()))();");
+}
+
+#[test]
+fn test_unwrap_try_which_cannot_throw() {
+    test("try { var a = 1; } catch (e) { log(e) }", "var a = 1;");
+    test(
+        "try { var a = 1, b = [2, 'c']; } catch (e) { var c = 3; }",
+        "var a = 1, b = [2, 'c']; var c;",
+    );
+    test(
+        "try { var a = 1; } catch (e) { log(e) } finally { f(); }",
+        "var a = 1; f();",
+    );
+    test(
+        "try { let a = 1; } finally { let b = 2; }",
+        "{ let a = 1; { let b = 2; } }",
+    );
+
+    test_same("try { var a = b; } catch (e) { log(e) }");
+    test_same("try { var a = f(); } catch (e) { log(e) }");
+    test_same("try { var a = b.c; } catch (e) { log(e) }");
+    test_same("function f() { try { return 1; } finally { g(); } }");
+}
+
+#[test]
+fn test_drop_debugger() {
+    let src = concat!(
+        "debugger;",
+        "function f() { debugger; g(); }",
+        "class A { foo() { debugger; } }",
+        "l: debugger;",
+        "if (a) debugger;",
+    );
+
+    test_same(src);
+    test_with(
+        Config {
+            drop_debugger: true,
+            ..Default::default()
+        },
+        src,
+        "function f() { g(); } class A { foo() {} }",
+    );
+}
+
+#[test]
+fn test_remove_unused_label() {
+    test("outer: for(;;){ if (a) break; }", "for(;;) if (a) break;");
+    test(
+        "outer: for(;;){ inner: for(;;) { if (a) break; } }",
+        "for(;;) for(;;) if (a) break;",
+    );
+    test("outer: { let x = 1; }", "{ let x = 1; }");
+    test(
+        "outer: for(;;){ function f() { outer: for(;;) break outer; } }",
+        "for(;;){ function f() { outer: for(;;) break outer; } }",
+    );
+
+    test_same("outer: for(;;){ break outer; }");
+    test_same("outer: for(;;){ for(;;) { continue outer; } }");
+    test_same("outer: for(;;){ inner: for(;;) { if (a) break outer; break inner; } }");
+}
+
+#[test]
+fn test_remove_loop_over_empty_collection() {
+    test("for (const k in {}) f(k);", "");
+    test("for (k in []) f(k);", "");
+    test("for (let v of []) { let x = f(v); }", "");
+    test("for (var k in {}) { var x = f(k); }", "var k, x;");
+    test("for (var [a = f()] of []) g(a);", "var a;");
+    test("function f() { for (var v of []) return v; }", "function f() { var v; }");
+
+    test_same("for (const k in a) f(k);");
+    test_same("for (const k in { a: 1 }) f(k);");
+    test_same("for (const v of [1]) f(v);");
+    // Object literals are not iterable.
+    test_same("for (const v of {}) f(v);");
+}
+
+#[test]
+fn test_keep_block_with_lexical_decl() {
+    test_same("{ const x = 1; } const x = 2;");
+    test("{ { let x = 1; } } let x = 2;", "{ let x = 1; } let x = 2;");
+    test("{ { const x = 1; } } const x = 2;", "{ const x = 1; } const x = 2;");
+    test("{ { class A {} } } class A {}", "{ class A {} } class A {}");
+    test("{ var x = 1; } var x = 2;", "var x = 1; var x = 2;");
+    test("{ { f(); } }", "f();");
+}
+
+#[test]
+fn test_fold_cond_expr() {
+    test_dce_only("var x = true ? f() : g();", "var x = f();");
+    test_dce_only("var x = 0 ? f() : g();", "var x = g();");
+    test_dce_only("var x = 'a' ? f() : g();", "var x = f();");
+    test_dce_only("var x = void h() ? f() : g();", "var x = (h(), g());");
+
+    test_dce_only("var x = a ? f() : g();", "var x = a ? f() : g();");
+    test_dce_only("var x = h() ? f() : g();", "var x = h() ? f() : g();");
+}
+
+#[test]
+fn test_keep_do_while_false_with_jump() {
+    test_same("do { if (x) break; f(); } while (false);");
+    test_same("do { if (x) continue; f(); } while (false);");
+    test_same("for (;;) { do { if (x) break; f(); } while (false); g(); }");
+    test_same("l: do { for (;;) { if (x) continue l; } } while (false);");
+
+    test("do { for (;;) { if (x) break; } f(); } while (false);", "for (;;) if (x) break; f();");
+    test(
+        "do { switch (x) { case 1: f(); break; } g(); } while (false);",
+        "switch (x) { case 1: f(); break; } g();",
+    );
+}
+
+#[test]
+fn test_remove_loop_executed_once() {
+    test("while (true) { a(); break; }", "a();");
+    test("for (;;) { a(); b(); break; }", "a(); b();");
+    test("for (;;) break;", "");
+    test("for (var i = 0;;) { a(i); break; }", "var i = 0; a(i);");
+    test("for (let i = 0;;) { a(i); break; }", "{ let i = 0; a(i); }");
+    test("while (true) { var x = a(); break; }", "var x = a();");
+    test("l: for (;;) { a(); break; }", "a();");
+
+    test_same("while (true) { if (a) continue; b(); break; }");
+    test_same("l: for (;;) { if (a) break l; b(); break; }");
+    test_same("for (;;) { if (a) break; b(); }");
+    test_same("while (true) { for (;;) { if (a) break; b(); } }");
+    test_same("while (f()) { a(); break; }");
+}
+
+#[test]
+fn test_keep_directives() {
+    test("function f() { 'use strict'; 0; }", "function f() { 'use strict'; }");
+    test("'use strict'; 0; f();", "'use strict'; f();");
+    test_same("function f() { 'use strict'; 'use asm'; g(); }");
+    test_same("var f = function () { 'use strict'; };");
+    test_same("var f = () => { 'use strict'; };");
+    test_same("class A { foo() { 'use strict'; } }");
+    test(
+        "if (a) { (function () { 'use strict'; g(); })(); }",
+        "if (a) (function () { 'use strict'; g(); })();",
+    );
+
+    // Not a directive
+    test("if (a) { 'use strict'; f(); }", "if (a) f();");
+}
+
+#[test]
+fn test_keep_spread_of_pure_callee() {
+    test("new Date(...effectfulIterable());", "[...effectfulIterable()];");
+    test("Math.max(...effectfulIterable());", "[...effectfulIterable()];");
+    test("(function () {})(...effectfulIterable());", "[...effectfulIterable()];");
+}
+
+/// [test_transform] drops comments, so annotations are tested with this.
+fn test_with_comments(src: &str, expected: &str) {
+    Tester::run(|tester| {
+        let module = tester.parse_module("input.js", src)?;
+        let module = {
+            let mut tr = chain!(
+                expr_simplifier(),
+                dce_with_comments(Default::default(), &tester.comments)
+            );
+            module.fold_with(&mut tr).fold_with(&mut fixer())
+        };
+        let actual = tester.print(&module);
+
+        let expected = {
+            let expected = tester.parse_module("expected.js", expected)?;
+            tester.print(&expected)
+        };
+
+        if actual != expected {
+            panic!(
+                "\n>>>>> Actual <<<<<\n{}\n>>>>> Expected <<<<<\n{}",
+                actual, expected
+            );
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_pure_annotation() {
+    test_with_comments("/*#__PURE__*/ factory();", "");
+    test_with_comments("/*@__PURE__*/ factory();", "");
+    test_with_comments("/*#__PURE__*/ factory(effect());", "effect();");
+    test_with_comments("/*#__PURE__*/ new Foo(effect());", "effect();");
+    test_with_comments("/*#__PURE__*/ factory(...effect());", "[...effect()];");
+
+    test_with_comments("factory(effect());", "factory(effect());");
+    test_with_comments("/* foo */ factory();", "factory();");
+    test_with_comments("var x = /*#__PURE__*/ factory();", "var x = factory();");
+
+    // Without comments, annotations are ignored.
+    test_same("/*#__PURE__*/ factory();");
+}
+
+#[test]
+fn test_unused_with_closures() {
+    // Captured by closures
+    test_unused(
+        "function f() { var a = 1; return () => a; }",
+        "function f() { var a = 1; return () => a; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return { get b() { return a; } }; }",
+        "function f() { var a = 1; return { get b() { return a; } }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return function () { return function () { return a; }; }; }",
+        "function f() { var a = 1; return function () { return function () { return a; }; }; }",
+    );
+
+    // Shadowed by closures
+    test_unused(
+        "function f() { var a = foo(); return function (a) { return a; }; }",
+        "function f() { foo(); return function (a) { return a; }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return function () { var a = 2; return a; }; }",
+        "function f() { return function () { var a = 2; return a; }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return (a) => a; }",
+        "function f() { return (a) => a; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return { set b(a) { g(a); } }; }",
+        "function f() { return { set b(a) { g(a); } }; }",
+    );
+    test_unused(
+        "function f() { var a = 1; return class { m() { function a() {} return a; } }; }",
+        "function f() { return class { m() { function a() {} return a; } }; }",
+    );
+    test_unused(
+        "function f() { var a = function a() { return a; }; }",
+        "function f() {}",
+    );
+
+    // `let` in a nested block does not shadow the outer variable.
+    test_unused(
+        "function f() { var a = 1; return function () { { let a = 2; } return a; }; }",
+        "function f() { var a = 1; return function () { { let a = 2; } return a; }; }",
+    );
+}
+
+#[test]
+fn test_keep_exported_bindings() {
+    let config = Config {
+        top_level: true,
+        unused: true,
+        ..Default::default()
+    };
+
+    test_with(
+        config,
+        "function helper() {} export { helper };",
+        "function helper() {} export { helper };",
+    );
+    test_with(
+        config,
+        "function helper() {} export { helper as default };",
+        "function helper() {} export { helper as default };",
+    );
+    test_with(
+        config,
+        "var a = foo(); var b = bar(); export { a as c };",
+        "var a = foo(); bar(); export { a as c };",
+    );
+    test_with(
+        config,
+        "var a = foo(); function f() {} export default a;",
+        "var a = foo(); export default a;",
+    );
+    test_with(
+        config,
+        "function f() { return g(); } function g() {} export const x = f;",
+        "function f() { return g(); } function g() {} export const x = f;",
+    );
+}
+
+#[test]
+fn test_keep_span_of_if_test() {
+    Tester::run(|tester| {
+        let module = tester.parse_module("input.js", "if ([f()]) g();")?;
+        let test_span = match module.body[0] {
+            ModuleItem::Stmt(Stmt::If(IfStmt { ref test, .. })) => test.span(),
+            _ => unreachable!(),
+        };
+
+        let module = module.fold_with(&mut dce());
+        match module.body[0] {
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt { span, .. })) => assert_eq!(span, test_span),
+            ref item => panic!("expected an expression statement, got {:?}", item),
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_remove_empty_trailing_cases() {
+    test(
+        "switch (x) { case 1: f(); break; case 2: case 3: default: }",
+        "switch (x) { case 1: f(); break; }",
+    );
+    test("switch (x) { case 1: f(); default: }", "switch (x) { case 1: f(); }");
+    test(
+        "switch (x) { case 1: f(); default: case 2: break; }",
+        "switch (x) { case 1: f(); case 2: break; }",
+    );
+    test(
+        "switch (x) { case 1: f(); break; case y: }",
+        "switch (x) { case 1: f(); break; }",
+    );
+
+    // Default falls through into `case 2`.
+    test_same("switch (x) { case 1: f(); default: case 2: g(); }");
+    // `x` may be `1`, which does not select the default.
+    test_same("switch (x) { default: f(); case 1: }");
+    test_same("switch (x) { case 1: f(); break; case g(): }");
+    test_same("switch (x) { case 1: a(); break; case Math.abs(f()): }");
+}
+
+#[test]
+fn test_merge_identical_if_branches() {
+    test("if (a) f(); else f();", "f();");
+    test("if (a()) { f(); } else { f(); }", "a(); f();");
+    test("if (a()) { f(); g(); } else { f(); g(); }", "a(); f(); g();");
+    test("if (a) f(); else if (b) f(); else f();", "f();");
+
+    test_same("if (a) f(); else g();");
+    test_same("if (a) f(1); else f(2);");
+    test_same("if (a) { f(); g(); } else { f(); }");
+    test_same("if (a) { let x = f(); g(x); } else { let x = f(); g(x); }");
+}
+
+#[test]
+fn test_remove_dead_stores() {
+    test_unused(
+        "function f() { var x; x = a(); x = b(); return x; }",
+        "function f() { var x; a(); x = b(); return x; }",
+    );
+    test_unused(
+        "function f() { var x; x = 1; x = b(); return x; }",
+        "function f() { var x; x = b(); return x; }",
+    );
+    test_unused(
+        "function f() { var x; x = a(); x = b(); x = c(); return x; }",
+        "function f() { var x; a(); b(); x = c(); return x; }",
+    );
+
+    test_unused(
+        "function f() { var x; x = a(); x = b(x); return x; }",
+        "function f() { var x; x = a(); x = b(x); return x; }",
+    );
+    test_unused(
+        "function f() { var x; x = a(); g(x); x = b(); return x; }",
+        "function f() { var x; x = a(); g(x); x = b(); return x; }",
+    );
+    test_unused(
+        "function f() { var x; function h() { return x; } x = a(); x = h(); return x; }",
+        "function f() { var x; function h() { return x; } x = a(); x = h(); return x; }",
+    );
+    test_unused(
+        "function f(x) { x = a(); x = g(arguments); return x; }",
+        "function f(x) { x = a(); x = g(arguments); return x; }",
+    );
+    test_unused(
+        "function f(o) { o.x = a(); o.x = b(); }",
+        "function f(o) { o.x = a(); o.x = b(); }",
+    );
+    test_unused(
+        "function f() { x = a(); x = b(); }",
+        "function f() { x = a(); x = b(); }",
+    );
+    test_unused(
+        "function f() { var x; try { x = a(); x = b(); } catch (e) { return x; } }",
+        "function f() { var x; try { x = a(); x = b(); } catch (e) { return x; } }",
+    );
+}
+
+#[test]
+fn test_split_seq_stmt() {
+    test("(a(), b(), c());", "a(); b(); c();");
+    test("(a(), 1, b());", "a(); b();");
+    test("(a(), x.y, b(), 2);", "a(); x.y; b();");
+    test("function f() { (a(), b()); }", "function f() { a(); b(); }");
+    test("if (x) { (a(), b()); }", "if (x) { a(); b(); }");
+
+    test_same("x = (a(), b());");
+    test_same("for (a(), b(); x; ) f();");
+    test_same("if (x) a(), b();");
+}
+
+#[test]
+fn test_remove_unused_arrow() {
+    test("() => { foo(); };", "");
+    test("async () => { await foo(); };", "");
+    test("(x => x, a(), () => b(), c());", "a(); c();");
+}
+
+#[test]
+fn test_remove_unused_class() {
+    let unused = Config {
+        unused: true,
+        ..Default::default()
+    };
+
+    test_unused("function f() { class A { foo() { return 1; } } }", "function f() {}");
+    test_ts_with(
+        unused,
+        "function f() { class A { static x = 1; y = g(); } }",
+        "function f() {}",
+    );
+
+    // Static initializer is evaluated when the class is defined.
+    test_ts_with(
+        unused,
+        "function f() { class A { static x = g(); } }",
+        "function f() { class A { static x = g(); } }",
+    );
+    // `B` may be undeclared or not a constructor.
+    test_unused(
+        "function f() { class A extends B {} }",
+        "function f() { class A extends B {} }",
+    );
+    test_unused(
+        "function f() { class A extends g() {} }",
+        "function f() { class A extends g() {} }",
+    );
+    test_unused(
+        "function f() { class A { [g()]() {} } }",
+        "function f() { class A { [g()]() {} } }",
+    );
+    test_unused(
+        "function f() { class A {} return new A(); }",
+        "function f() { class A {} return new A(); }",
+    );
+
+    let top_level = Config {
+        unused: true,
+        top_level: true,
+        ..Default::default()
+    };
+    test_with(top_level, "class A {} export { A };", "class A {} export { A };");
+    test_with(top_level, "export class A {}", "export class A {}");
+}
+
+#[test]
+fn test_remove_unused_imports() {
+    let unused_imports = Config {
+        unused_imports: true,
+        ..Default::default()
+    };
+
+    test_with(
+        unused_imports,
+        "import { a, b } from 'm'; a();",
+        "import { a } from 'm'; a();",
+    );
+    test_with(
+        unused_imports,
+        "import d, * as ns from 'm'; ns.f();",
+        "import * as ns from 'm'; ns.f();",
+    );
+    test_with(
+        unused_imports,
+        "import d, { a as b } from 'm'; d();",
+        "import d from 'm'; d();",
+    );
+    test_with(unused_imports, "import { a, b } from 'm'; f();", "import 'm'; f();");
+    test_with(unused_imports, "import { a } from 'm'; if (false) a();", "import 'm';");
+    test_with(unused_imports, "import 'm';", "import 'm';");
+    test_with(
+        unused_imports,
+        "import { a } from 'm'; export { a };",
+        "import { a } from 'm'; export { a };",
+    );
+    test_with(
+        unused_imports,
+        "import { a } from 'm'; eval('a');",
+        "import { a } from 'm'; eval('a');",
+    );
+
+    let drop_empty_imports = Config {
+        unused_imports: true,
+        drop_empty_imports: true,
+        ..Default::default()
+    };
+    test_with(
+        drop_empty_imports,
+        "import { a } from 'm'; import 'n'; import * as o from 'o'; o.f();",
+        "import 'n'; import * as o from 'o'; o.f();",
+    );
+
+    test_same("import { a } from 'm';");
+}
+
+#[test]
+fn test_accessor_scope() {
+    test_unused(
+        "function f() { var x; return { get a() { var x = 1; return x; } }; }",
+        "function f() { return { get a() { var x = 1; return x; } }; }",
+    );
+    test_unused(
+        "function f() { var x; return { set a(v) { var x = v; g(x); } }; }",
+        "function f() { return { set a(v) { var x = v; g(x); } }; }",
+    );
+
+    test_unused(
+        "function f() { return { get a() { function g() {} return g(); } }; }",
+        "function f() { return { get a() { function g() {} return g(); } }; }",
+    );
+    test_unused(
+        "function f() { return { set a(v) { function g() {} g(v); } }; }",
+        "function f() { return { set a(v) { function g() {} g(v); } }; }",
+    );
+
+    test_same("var o = { get a() { 'use strict'; return 1; } };");
+    test_same("var o = { set a(v) { 'use strict'; g(v); } };");
+}
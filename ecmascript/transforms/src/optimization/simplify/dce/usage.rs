@@ -0,0 +1,346 @@
+use crate::util::{find_ids, id, var::VarCollector, Id};
+use ast::*;
+use fxhash::FxHashMap;
+use std::mem::replace;
+use swc_atoms::js_word;
+use swc_common::{Visit, VisitWith};
+
+/// Usage information of variables declared in a function.
+#[derive(Debug, Default)]
+pub(super) struct Scope {
+    vars: FxHashMap<Id, VarInfo>,
+    /// True if `eval` or `with` is used, which means that any variable can be
+    /// referenced by name.
+    has_eval: bool,
+    /// True if `arguments` is used, which aliases parameters in sloppy mode.
+    /// Parameters are not distinguished from other variables, so every
+    /// variable is treated as referenced.
+    uses_arguments: bool,
+}
+
+#[derive(Debug, Default)]
+struct VarInfo {
+    /// Number of references, excluding the declaration itself.
+    cnt: usize,
+    /// True if referenced from a nested function.
+    captured: bool,
+}
+
+impl Scope {
+    /// Collects variables declared in `node` and counts references to them.
+    ///
+    /// References from nested functions are counted, as those functions may
+    /// be called at any time, unless the nested function declares a variable
+    /// with the same name.
+    pub fn analyze<T>(node: &T) -> Self
+    where
+        T: for<'any> VisitWith<VarCollector<'any>> + VisitWith<UsageCounter>,
+    {
+        let mut ids = vec![];
+        // `VarCollector` does not descend into functions, so we visit children
+        // of `node` directly.
+        node.visit_children(&mut VarCollector { to: &mut ids });
+
+        let mut v = UsageCounter {
+            scope: Scope {
+                vars: ids.into_iter().map(|i| (i, VarInfo::default())).collect(),
+                has_eval: false,
+                uses_arguments: false,
+            },
+            in_nested_fn: false,
+        };
+        node.visit_children(&mut v);
+        v.scope
+    }
+
+    /// Returns true if `i` is declared in this scope and is never referenced.
+    pub fn is_unused(&self, i: &Ident) -> bool {
+        self.ref_count(&id(i)) == Some(0)
+    }
+
+    /// Returns the number of references to `i`.
+    ///
+    /// Returns [None] if `i` is not declared in this scope, or if it can be
+    /// referenced by name or through `arguments`.
+    pub fn ref_count(&self, i: &Id) -> Option<usize> {
+        if self.has_eval || self.uses_arguments {
+            return None;
+        }
+
+        self.vars.get(i).map(|info| info.cnt)
+    }
+
+    /// Returns true if `i` is declared in this scope and can be accessed only
+    /// from this function, not from nested functions or `arguments`.
+    pub fn is_local(&self, i: &Id) -> bool {
+        !self.has_eval
+            && !self.uses_arguments
+            && self.vars.get(i).map_or(false, |info| !info.captured)
+    }
+}
+
+/// Counts references to `ids` in `node`.
+pub(super) fn count_refs<T>(node: &T, ids: &[Id]) -> FxHashMap<Id, usize>
+where
+    T: VisitWith<UsageCounter>,
+{
+    let mut v = UsageCounter {
+        scope: Scope {
+            vars: ids.iter().map(|i| (i.clone(), VarInfo::default())).collect(),
+            has_eval: false,
+            uses_arguments: false,
+        },
+        in_nested_fn: false,
+    };
+    node.visit_with(&mut v);
+
+    v.scope
+        .vars
+        .into_iter()
+        .map(|(i, info)| (i, info.cnt))
+        .collect()
+}
+
+pub(super) struct UsageCounter {
+    scope: Scope,
+    in_nested_fn: bool,
+}
+
+impl UsageCounter {
+    /// Visits a nested function using `op`.
+    ///
+    /// References to `ids` are not counted, because `ids` are shadowed by the
+    /// nested function.
+    fn visit_nested_fn<F>(&mut self, ids: Vec<Id>, op: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let shadowed: Vec<_> = ids
+            .into_iter()
+            .filter_map(|i| self.scope.vars.remove_entry(&i))
+            .collect();
+        let old = replace(&mut self.in_nested_fn, true);
+
+        op(self);
+
+        self.in_nested_fn = old;
+        self.scope.vars.extend(shadowed);
+    }
+}
+
+/// Returns ids declared at the top level of a function body.
+///
+/// `var`s in nested blocks are ignored, which means that references to them
+/// are counted as references to the outer variable. This is conservative.
+fn declared_ids(stmts: &[Stmt]) -> Vec<Id> {
+    let mut ids = vec![];
+
+    for s in stmts {
+        match *s {
+            Stmt::Decl(Decl::Var(ref v)) => ids.extend(find_ids::<_, Id>(&v.decls)),
+            Stmt::Decl(Decl::Fn(ref f)) => ids.push(id(&f.ident)),
+            Stmt::Decl(Decl::Class(ref c)) => ids.push(id(&c.ident)),
+            _ => {}
+        }
+    }
+
+    ids
+}
+
+impl Visit<Function> for UsageCounter {
+    fn visit(&mut self, f: &Function) {
+        // Decorators are evaluated in the outer scope.
+        f.decorators.visit_with(self);
+
+        let mut ids: Vec<Id> = find_ids(&f.params);
+        if let Some(ref body) = f.body {
+            ids.extend(declared_ids(&body.stmts));
+        }
+
+        self.visit_nested_fn(ids, |v| {
+            f.params.visit_with(v);
+            f.body.visit_with(v);
+            f.type_params.visit_with(v);
+            f.return_type.visit_with(v);
+        });
+    }
+}
+
+impl Visit<FnExpr> for UsageCounter {
+    fn visit(&mut self, e: &FnExpr) {
+        // Name of a function expression is only visible from the function.
+        let ids = e.ident.iter().map(id).collect();
+        self.visit_nested_fn(ids, |v| e.function.visit_with(v));
+    }
+}
+
+impl Visit<ArrowExpr> for UsageCounter {
+    fn visit(&mut self, f: &ArrowExpr) {
+        let mut ids: Vec<Id> = find_ids(&f.params);
+        if let BlockStmtOrExpr::BlockStmt(ref b) = f.body {
+            ids.extend(declared_ids(&b.stmts));
+        }
+
+        self.visit_nested_fn(ids, |v| f.visit_children(v));
+    }
+}
+
+impl Visit<Constructor> for UsageCounter {
+    fn visit(&mut self, c: &Constructor) {
+        c.key.visit_with(self);
+
+        let mut ids: Vec<Id> = find_ids(&c.params);
+        if let Some(ref body) = c.body {
+            ids.extend(declared_ids(&body.stmts));
+        }
+
+        self.visit_nested_fn(ids, |v| {
+            c.params.visit_with(v);
+            c.body.visit_with(v);
+        });
+    }
+}
+
+impl Visit<GetterProp> for UsageCounter {
+    fn visit(&mut self, p: &GetterProp) {
+        p.key.visit_with(self);
+
+        let ids = match p.body {
+            Some(ref body) => declared_ids(&body.stmts),
+            None => vec![],
+        };
+
+        self.visit_nested_fn(ids, |v| {
+            p.type_ann.visit_with(v);
+            p.body.visit_with(v);
+        });
+    }
+}
+
+impl Visit<SetterProp> for UsageCounter {
+    fn visit(&mut self, p: &SetterProp) {
+        p.key.visit_with(self);
+
+        let mut ids: Vec<Id> = find_ids(&p.param);
+        if let Some(ref body) = p.body {
+            ids.extend(declared_ids(&body.stmts));
+        }
+
+        self.visit_nested_fn(ids, |v| {
+            p.param.visit_with(v);
+            p.body.visit_with(v);
+        });
+    }
+}
+
+impl Visit<Ident> for UsageCounter {
+    fn visit(&mut self, i: &Ident) {
+        if i.sym == js_word!("arguments") {
+            self.scope.uses_arguments = true;
+        }
+
+        if let Some(info) = self.scope.vars.get_mut(&id(i)) {
+            info.cnt += 1;
+            info.captured |= self.in_nested_fn;
+        }
+    }
+}
+
+impl Visit<VarDeclarator> for UsageCounter {
+    fn visit(&mut self, d: &VarDeclarator) {
+        match d.name {
+            // Binding itself is not a reference.
+            Pat::Ident(..) => {}
+            // Default values may reference variables.
+            _ => d.name.visit_with(self),
+        }
+
+        d.init.visit_with(self);
+    }
+}
+
+impl Visit<FnDecl> for UsageCounter {
+    fn visit(&mut self, d: &FnDecl) {
+        // Name of the function is not a reference.
+        d.function.visit_with(self);
+    }
+}
+
+impl Visit<ClassDecl> for UsageCounter {
+    fn visit(&mut self, d: &ClassDecl) {
+        // Name of the class is not a reference.
+        d.class.visit_with(self);
+    }
+}
+
+impl Visit<ImportDecl> for UsageCounter {
+    fn visit(&mut self, _: &ImportDecl) {
+        // Bindings created by imports are not references.
+    }
+}
+
+impl Visit<MemberExpr> for UsageCounter {
+    fn visit(&mut self, e: &MemberExpr) {
+        e.obj.visit_with(self);
+
+        if e.computed {
+            e.prop.visit_with(self);
+        }
+    }
+}
+
+impl Visit<PropName> for UsageCounter {
+    fn visit(&mut self, n: &PropName) {
+        match n {
+            PropName::Computed(..) => n.visit_children(self),
+            _ => {}
+        }
+    }
+}
+
+impl Visit<ClassProp> for UsageCounter {
+    fn visit(&mut self, p: &ClassProp) {
+        if p.computed {
+            p.key.visit_with(self);
+        }
+
+        // Evaluated when the class is instantiated, like a function.
+        self.visit_nested_fn(vec![], |v| p.value.visit_with(v));
+        p.type_ann.visit_with(self);
+        p.decorators.visit_with(self);
+    }
+}
+
+impl Visit<LabeledStmt> for UsageCounter {
+    fn visit(&mut self, s: &LabeledStmt) {
+        s.body.visit_with(self);
+    }
+}
+
+impl Visit<BreakStmt> for UsageCounter {
+    fn visit(&mut self, _: &BreakStmt) {}
+}
+
+impl Visit<ContinueStmt> for UsageCounter {
+    fn visit(&mut self, _: &ContinueStmt) {}
+}
+
+impl Visit<CallExpr> for UsageCounter {
+    fn visit(&mut self, e: &CallExpr) {
+        match e.callee {
+            ExprOrSuper::Expr(box Expr::Ident(ref i)) if i.sym == js_word!("eval") => {
+                self.scope.has_eval = true;
+            }
+            _ => {}
+        }
+
+        e.visit_children(self);
+    }
+}
+
+impl Visit<WithStmt> for UsageCounter {
+    fn visit(&mut self, s: &WithStmt) {
+        self.scope.has_eval = true;
+        s.visit_children(self);
+    }
+}
@@ -118,12 +118,48 @@ pub trait ModuleItemLike: StmtLike {
     fn try_from_module_decl(decl: ModuleDecl) -> Result<Self, ModuleDecl> {
         Err(decl)
     }
+    fn as_module_decl(&self) -> Option<&ModuleDecl> {
+        None
+    }
+
+    /// Returns true for typescript-only module declarations.
+    ///
+    /// ```ts
+    /// import foo = require('foo');
+    /// export = foo;
+    /// export as namespace foo;
+    /// ```
+    fn is_ts_module_decl(&self) -> bool {
+        match self.as_module_decl() {
+            Some(ModuleDecl::TsImportEquals(..))
+            | Some(ModuleDecl::TsExportAssignment(..))
+            | Some(ModuleDecl::TsNamespaceExport(..)) => true,
+            _ => false,
+        }
+    }
 }
 
 pub trait StmtLike: Sized + 'static {
     fn try_into_stmt(self) -> Result<Stmt, Self>;
     fn as_stmt(&self) -> Option<&Stmt>;
+    /// Returns [None] by default, which means that `self` cannot be modified
+    /// in place.
+    fn as_stmt_mut(&mut self) -> Option<&mut Stmt> {
+        None
+    }
     fn from_stmt(stmt: Stmt) -> Self;
+
+    /// Applies `op` if `self` is a statement, and returns `self` as-is
+    /// otherwise.
+    fn map_stmt<F>(self, op: F) -> Self
+    where
+        F: FnOnce(Stmt) -> Stmt,
+    {
+        match self.try_into_stmt() {
+            Ok(stmt) => Self::from_stmt(op(stmt)),
+            Err(item) => item,
+        }
+    }
 }
 
 impl ModuleItemLike for Stmt {}
@@ -135,6 +171,9 @@ impl StmtLike for Stmt {
     fn as_stmt(&self) -> Option<&Stmt> {
         Some(&self)
     }
+    fn as_stmt_mut(&mut self) -> Option<&mut Stmt> {
+        Some(self)
+    }
     fn from_stmt(stmt: Stmt) -> Self {
         stmt
     }
@@ -150,6 +189,12 @@ impl ModuleItemLike for ModuleItem {
     fn try_from_module_decl(decl: ModuleDecl) -> Result<Self, ModuleDecl> {
         Ok(ModuleItem::ModuleDecl(decl))
     }
+    fn as_module_decl(&self) -> Option<&ModuleDecl> {
+        match *self {
+            ModuleItem::ModuleDecl(ref decl) => Some(decl),
+            _ => None,
+        }
+    }
 }
 impl StmtLike for ModuleItem {
     fn try_into_stmt(self) -> Result<Stmt, Self> {
@@ -164,6 +209,12 @@ impl StmtLike for ModuleItem {
             _ => None,
         }
     }
+    fn as_stmt_mut(&mut self) -> Option<&mut Stmt> {
+        match *self {
+            ModuleItem::Stmt(ref mut stmt) => Some(stmt),
+            _ => None,
+        }
+    }
     fn from_stmt(stmt: Stmt) -> Self {
         ModuleItem::Stmt(stmt)
     }
@@ -1700,3 +1751,47 @@ pub fn prop_name_eq(p: &PropName, key: &str) -> bool {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_common::FileName;
+    use swc_ecma_parser::{Parser, Session, SourceFileInput, Syntax};
+
+    fn parse_ts_module(src: &str) -> Module {
+        ::testing::run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut p = Parser::new(
+                Session { handler },
+                Syntax::Typescript(Default::default()),
+                SourceFileInput::from(&*fm),
+                None,
+            );
+            p.parse_module().map_err(|mut e| {
+                e.emit();
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn ts_module_decl() {
+        let m = parse_ts_module(
+            "import foo = require('foo');
+            import { bar } from 'bar';
+            foo();
+            export = bar;",
+        );
+
+        let kinds = m
+            .body
+            .iter()
+            .map(|item| (item.as_module_decl().is_some(), item.is_ts_module_decl()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![(true, true), (true, false), (false, false), (true, true)]
+        );
+    }
+}
@@ -2,7 +2,7 @@ use crate::{builder::PassBuilder, error::Error};
 use atoms::JsWord;
 use chashmap::CHashMap;
 pub use common::chain;
-use common::{errors::Handler, FileName, SourceMap};
+use common::{comments::Comments, errors::Handler, FileName, SourceMap};
 pub use ecmascript::parser::JscTarget;
 use ecmascript::{
     ast::{Expr, ExprStmt, ModuleItem, Stmt},
@@ -10,7 +10,7 @@ use ecmascript::{
     preset_env,
     transforms::{
         const_modules, modules,
-        optimization::{simplifier, InlineGlobals, JsonParse},
+        optimization::{simplifier_with_comments, InlineGlobals, JsonParse},
         pass::{noop, Optional, Pass},
         proposals::{class_properties, decorators, export, nullish_coalescing, optional_chaining},
         react, resolver, typescript,
@@ -125,13 +125,14 @@ impl Default for InputSourceMap {
 }
 
 impl Options {
-    pub fn build(
+    pub fn build<'a>(
         &self,
         cm: &Arc<SourceMap>,
         handler: &Handler,
+        comments: &'a Comments,
         is_module: bool,
         config: Option<Config>,
-    ) -> BuiltConfig<impl Pass> {
+    ) -> BuiltConfig<impl Pass + 'a> {
         let mut config = config.unwrap_or_else(Default::default);
         if let Some(ref c) = self.config {
             config.merge(c)
@@ -206,7 +207,7 @@ impl Options {
                 export(),
                 syntax.export_default_from() || syntax.export_namespace_from()
             ),
-            Optional::new(simplifier(), enable_optimizer),
+            Optional::new(simplifier_with_comments(comments), enable_optimizer),
             json_parse_pass
         );
 
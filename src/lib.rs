@@ -205,7 +205,7 @@ impl Compiler {
         &self,
         opts: &Options,
         fm: &SourceFile,
-    ) -> Result<BuiltConfig<impl Pass>, Error> {
+    ) -> Result<BuiltConfig<impl Pass + '_>, Error> {
         let Options {
             ref root,
             root_mode,
@@ -246,8 +246,13 @@ impl Compiler {
                             if let Some(config_file) = config_file {
                                 config.merge(&config_file.into_config(Some(path))?)
                             }
-                            let built =
-                                opts.build(&self.cm, &self.handler, *is_module, Some(config));
+                            let built = opts.build(
+                                &self.cm,
+                                &self.handler,
+                                &self.comments,
+                                *is_module,
+                                Some(config),
+                            );
                             return Ok(built);
                         }
 
@@ -262,6 +267,7 @@ impl Compiler {
                     let built = opts.build(
                         &self.cm,
                         &self.handler,
+                        &self.comments,
                         *is_module,
                         Some(config_file.into_config(Some(path))?),
                     );
@@ -274,6 +280,7 @@ impl Compiler {
         let built = opts.build(
             &self.cm,
             &self.handler,
+            &self.comments,
             *is_module,
             match config_file {
                 Some(config_file) => Some(config_file.into_config(None)?),